@@ -0,0 +1,55 @@
+use ::base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine,
+};
+
+pub use ::base64::DecodeError;
+
+/// encodes `bytes` as standard base64 (`+/` alphabet, `=` padded), as used by sql::query_to_json for BLOBs
+pub fn encode(bytes: &[u8]) -> String {
+    STANDARD.encode(bytes)
+}
+
+/// decodes standard base64, as produced by encode. Padding is required
+pub fn decode(s: &str) -> Result<Vec<u8>, DecodeError> {
+    STANDARD.decode(s)
+}
+
+/// encodes `bytes` as url safe base64 (`-_` alphabet, no padding), for use in urls and file names
+pub fn encode_url_safe(bytes: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// decodes url safe base64 without padding, as produced by encode_url_safe
+pub fn decode_url_safe(s: &str) -> Result<Vec<u8>, DecodeError> {
+    URL_SAFE_NO_PAD.decode(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode_round_trip() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        assert_eq!(decode(&encode(&bytes)).unwrap(), bytes);
+        assert_eq!(encode(b"hello"), "aGVsbG8=");
+        assert_eq!(encode(b""), "");
+    }
+
+    #[test]
+    fn test_encode_decode_url_safe_round_trip() {
+        let bytes = [0xfb, 0xff, 0xfe];
+        assert_eq!(encode(&bytes), "+//+");
+        assert_eq!(encode_url_safe(&bytes), "-__-");
+        assert_eq!(decode_url_safe("-__-").unwrap(), bytes);
+        assert_eq!(encode_url_safe(b"hello"), "aGVsbG8");
+    }
+
+    #[test]
+    fn test_decode_error() {
+        assert!(matches!(decode("aGVsbG8"), Err(DecodeError::InvalidPadding)));
+        assert!(matches!(decode("a$=="), Err(DecodeError::InvalidByte(1, b'$'))));
+        assert!(decode_url_safe("+//+").is_err());
+    }
+}
@@ -1,33 +1,312 @@
-use arboard::Clipboard;
+use crate::HelperError;
+use arboard::{Clipboard, ImageData};
+use std::{
+	borrow::Cow,
+	fs,
+	io,
+	path::Path,
+	sync::{atomic::{AtomicBool, Ordering}, Arc},
+	time::Duration,
+};
 
 #[cfg(target_os = "linux")]
 use arboard::SetExtLinux;
-#[cfg(target_os = "linux")]
 use std::thread;
 
-pub fn copy_text(text: String) {
+/// whether a clipboard can be opened, false e.g. in headless CI or an SSH session without a display
+pub fn clipboard_available() -> bool {
+	Clipboard::new().is_ok()
+}
+
+/// copies `text` to the clipboard. Errors if there is no clipboard to copy to (e.g. a headless session).
+/// On Linux the copy is asynchronous: Ok means the clipboard was opened and a background thread has been spawned to
+/// serve the text, so a failure after that point is not reported
+pub fn copy_text(text: String) -> Result<(), HelperError> {
 	// https://github.com/1Password/arboard/blob/master/README.md
 
 	//linux clipboard manager (X11 and Wayland) does not hold the clipboard contents, this stays with the initiating app, so hold a thread open with the Clipboard object.
 	#[cfg(target_os = "linux")]
 	{
-		thread::spawn(move || {threaded_copy_text(text);});
+		let ctx = Clipboard::new()?;
+		thread::spawn(move || {threaded_copy_text(ctx, text);});
+		Ok(())
 	}
 
 	//windows and macos clipboard  manager hold the clipboard contents, so once copied to the clipboard, it stays there.  No need to keep the apps Clipboard alive.
-	#[cfg(target_os = "windows")]
+	#[cfg(not(target_os = "linux"))]
 	{
-		if let Ok(mut ctx) = Clipboard::new() {
-			_ = ctx.set_text(text);
-		}
+		Ok(Clipboard::new()?.set_text(text)?)
+	}
+}
+
+/// as copy_text, but on Linux returns the handle of the thread serving the text, which ends once another copy replaces it.
+/// A CLI that copies then exits straight away can `.join()` it (e.g. from a thread it stops waiting on after a while) so the
+/// text can still be pasted. None on other platforms, where the copy is complete on return. Errors are not reported, use
+/// copy_text for those
+pub fn copy_text_handle(text: String) -> Option<thread::JoinHandle<()>> {
+	#[cfg(target_os = "linux")]
+	{
+		Some(thread::spawn(move || {
+			if let Ok(ctx) = Clipboard::new() {
+				threaded_copy_text(ctx, text);
+			}
+		}))
+	}
+
+	#[cfg(not(target_os = "linux"))]
+	{
+		_ = copy_text(text);
+		None
 	}
 }
 
+/// as copy_text, for a borrowed string such as a literal: `copy_str("hello")`
+pub fn copy_str(text: &str) -> Result<(), HelperError> {
+	copy_text(text.to_owned())
+}
+
 #[cfg(target_os = "linux")]
-fn threaded_copy_text(text: String) {
+fn threaded_copy_text(mut ctx: Clipboard, text: String) {
 	//this thread keeps the clipboard source active until the clipboard is used again.
 	//It will auto-exit once ctx.set.wait ends.
-	if let Ok(mut ctx) = Clipboard::new() {
-		_ = ctx.set().wait().text(text);
+	_ = ctx.set().wait().text(text);
+}
+
+/// copies `html` to the clipboard for apps that paste formatted text, with `alt_text` as the plain text for those that don't.
+/// As copy_text, the copy is asynchronous on Linux
+pub fn copy_html(html: &str, alt_text: &str) -> Result<(), HelperError> {
+	let html = html.to_owned();
+	let alt_text = alt_text.to_owned();
+
+	#[cfg(target_os = "linux")]
+	{
+		let ctx = Clipboard::new()?;
+		thread::spawn(move || {threaded_copy_html(ctx, html, alt_text);});
+		Ok(())
+	}
+
+	#[cfg(not(target_os = "linux"))]
+	{
+		Ok(Clipboard::new()?.set_html(html, Some(alt_text))?)
+	}
+}
+
+#[cfg(target_os = "linux")]
+fn threaded_copy_html(mut ctx: Clipboard, html: String, alt_text: String) {
+	//as threaded_copy_text
+	_ = ctx.set().wait().html(html, Some(alt_text));
+}
+
+/// copies an image, given as `width` x `height` RGBA pixels row by row (4 bytes each), to the clipboard.
+/// Errors with ConversionFailure if `rgba` is not that size. As copy_text, the copy is asynchronous on Linux
+pub fn copy_image(width: usize, height: usize, rgba: Vec<u8>) -> Result<(), HelperError> {
+	if width.checked_mul(height).and_then(|pixels| pixels.checked_mul(4)) != Some(rgba.len()) {
+		return Err(HelperError::Clipboard(arboard::Error::ConversionFailure));
+	}
+	let image = ImageData { width, height, bytes: Cow::Owned(rgba) };
+
+	#[cfg(target_os = "linux")]
+	{
+		let ctx = Clipboard::new()?;
+		thread::spawn(move || {threaded_copy_image(ctx, image);});
+		Ok(())
+	}
+
+	#[cfg(not(target_os = "linux"))]
+	{
+		Ok(Clipboard::new()?.set_image(image)?)
+	}
+}
+
+#[cfg(target_os = "linux")]
+fn threaded_copy_image(mut ctx: Clipboard, image: ImageData<'static>) {
+	//as threaded_copy_text
+	_ = ctx.set().wait().image(image);
+}
+
+/// empties the clipboard. Errors if there is no clipboard (e.g. a headless session).
+/// On Linux the contents belong to the app that copied them, as with copy_text; clearing takes the selection away from it,
+/// which also ends the background thread of an earlier copy_text
+pub fn clear_clipboard() -> Result<(), HelperError> {
+	Ok(Clipboard::new()?.clear()?)
+}
+
+/// the current clipboard text, None if the clipboard can't be opened, holds no text, or the text is empty
+pub fn get_text() -> Option<String> {
+	let mut ctx = Clipboard::new().ok()?;
+	ctx.get_text().ok().filter(|text| !text.is_empty())
+}
+
+/// where copy_text_or_file put the text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyOutcome {
+	Clipboard,
+	File,
+}
+
+/// copies `text` to the clipboard, or writes it to `fallback` if there is no clipboard (e.g. over SSH), so a CLI can tell
+/// the user where to find it. Errors only if the fallback file can't be written
+pub fn copy_text_or_file(text: &str, fallback: &Path) -> Result<CopyOutcome, io::Error> {
+	copy_text_or_file_with(text, fallback, copy_str)
+}
+
+fn copy_text_or_file_with<F>(text: &str, fallback: &Path, copy: F) -> Result<CopyOutcome, io::Error>
+where
+	F: FnOnce(&str) -> Result<(), HelperError>,
+{
+	match copy(text) {
+		Ok(()) => Ok(CopyOutcome::Clipboard),
+		Err(_) => {
+			fs::write(fallback, text)?;
+			Ok(CopyOutcome::File)
+		},
+	}
+}
+
+/// how often watch_clipboard reads the clipboard
+pub const CLIPBOARD_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// calls `on_change` with the clipboard text each time it changes, starting with the text already there, until
+/// `keep_going` is false, e.g. for a clipboard history. The clipboard is polled every CLIPBOARD_POLL_INTERVAL, so a change
+/// that is replaced within that time is missed, and an emptied clipboard is not reported. On Linux the text belongs to the
+/// app that copied it and disappears when that app exits (see copy_text), so it can only be seen while the app is running
+pub fn watch_clipboard<F: FnMut(String)>(keep_going: Arc<AtomicBool>, on_change: F) {
+	watch_clipboard_with(&keep_going, CLIPBOARD_POLL_INTERVAL, get_text, on_change);
+}
+
+/// core of watch_clipboard, reading the clipboard with `read`
+fn watch_clipboard_with<R, F>(keep_going: &AtomicBool, interval: Duration, mut read: R, mut on_change: F)
+where
+	R: FnMut() -> Option<String>,
+	F: FnMut(String),
+{
+	let mut last_seen: Option<String> = None;
+	while keep_going.load(Ordering::Relaxed) {
+		if let Some(text) = read()
+			&& last_seen.as_ref() != Some(&text) {
+			last_seen = Some(text.clone());
+			on_change(text);
+		}
+		thread::sleep(interval);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_clipboard_available() {
+		//either answer is fine, but it must agree with what copying does
+		if !clipboard_available() {
+			assert!(copy_str("not copied").is_err());
+		}
+	}
+
+	#[test]
+	#[ignore = "needs a desktop session with a clipboard"]
+	fn test_copy_then_get_text() {
+		copy_text(String::from("helper_lib clipboard round trip")).unwrap();
+		//on linux the copy is made from a background thread
+		std::thread::sleep(std::time::Duration::from_millis(200));
+		assert_eq!(get_text(), Some(String::from("helper_lib clipboard round trip")));
+	}
+
+	#[test]
+	#[ignore = "needs a desktop session with a clipboard"]
+	fn test_copy_str_literal() {
+		copy_str("helper_lib copy_str").unwrap();
+		std::thread::sleep(std::time::Duration::from_millis(200));
+		assert_eq!(get_text().as_deref(), Some("helper_lib copy_str"));
+	}
+
+	#[test]
+	#[ignore = "needs a desktop session with a clipboard"]
+	fn test_clear_clipboard() {
+		copy_str("helper_lib sensitive value").unwrap();
+		std::thread::sleep(std::time::Duration::from_millis(200));
+		clear_clipboard().unwrap();
+		std::thread::sleep(std::time::Duration::from_millis(200));
+		assert_eq!(get_text(), None);
+	}
+
+	#[test]
+	#[ignore = "needs a desktop session with a clipboard"]
+	fn test_copy_html() {
+		copy_html("<b>helper_lib</b> html", "helper_lib html").unwrap();
+		std::thread::sleep(std::time::Duration::from_millis(200));
+		assert!(get_text().is_some());
+	}
+
+	#[test]
+	#[ignore = "needs a desktop session with a clipboard"]
+	fn test_copy_image_one_pixel() {
+		copy_image(1, 1, vec![255, 0, 0, 255]).unwrap();
+	}
+
+	#[test]
+	fn test_copy_image_wrong_size() {
+		assert!(matches!(copy_image(2, 2, vec![255, 0, 0, 255]), Err(HelperError::Clipboard(arboard::Error::ConversionFailure))));
+	}
+
+	#[cfg(target_os = "linux")]
+	#[test]
+	fn test_copy_text_handle_linux() {
+		let handle = copy_text_handle(String::from("helper_lib copy_text_handle"));
+		assert!(handle.is_some());
+	}
+
+	#[cfg(target_os = "linux")]
+	#[test]
+	fn test_copy_text_no_clipboard() {
+		//only meaningful in a headless session
+		if std::env::var_os("DISPLAY").is_some() || std::env::var_os("WAYLAND_DISPLAY").is_some() {
+			return;
+		}
+		assert!(copy_text(String::from("nowhere to copy to")).is_err());
+	}
+
+	#[test]
+	fn test_copy_text_or_file_fallback() {
+		let fallback = std::env::temp_dir().join("helper_lib_test_copy_text_or_file.txt");
+		_ = fs::remove_file(&fallback);
+		let outcome = copy_text_or_file_with("saved instead", &fallback, |_| Err(HelperError::Clipboard(arboard::Error::ClipboardNotSupported)));
+		assert_eq!(outcome.unwrap(), CopyOutcome::File);
+		assert_eq!(fs::read_to_string(&fallback).unwrap(), "saved instead");
+		fs::remove_file(&fallback).unwrap();
+	}
+
+	#[test]
+	fn test_copy_text_or_file_clipboard() {
+		let fallback = std::env::temp_dir().join("helper_lib_test_copy_text_or_file_unused.txt");
+		_ = fs::remove_file(&fallback);
+		let outcome = copy_text_or_file_with("copied", &fallback, |_| Ok(()));
+		assert_eq!(outcome.unwrap(), CopyOutcome::Clipboard);
+		assert!(!fallback.exists());
+	}
+
+	#[test]
+	fn test_watch_clipboard_with_changes() {
+		let keep_going = AtomicBool::new(true);
+		let mut readings = vec![Some("a"), Some("a"), None, Some("b"), Some("a"), Some("a")].into_iter();
+		let mut changes: Vec<String> = Vec::new();
+		watch_clipboard_with(&keep_going, Duration::ZERO, || {
+			match readings.next() {
+				Some(reading) => reading.map(String::from),
+				None => {
+					keep_going.store(false, Ordering::Relaxed);
+					None
+				},
+			}
+		}, |text| changes.push(text));
+		assert_eq!(changes, vec!["a", "b", "a"]);
+	}
+
+	#[test]
+	fn test_watch_clipboard_stopped() {
+		let keep_going = Arc::new(AtomicBool::new(false));
+		let mut changes: Vec<String> = Vec::new();
+		watch_clipboard(keep_going, |text| changes.push(text));
+		assert!(changes.is_empty());
 	}
 }
@@ -1,4 +1,4 @@
-﻿use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc};
+﻿use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc, Weekday};
 use std::time::{SystemTime};
 
 /// assumes naivedatetime is in UTC timezone
@@ -16,8 +16,7 @@ pub fn naivedatetime_to_local(naive_datetime: NaiveDateTime) -> DateTime<Local>
 			// This case handles a non-existent time during a DST spring-forward gap.
 			// If the date is one that falls in the gap, we return the NaiveDateTime
 			// as the next hour
-			let local_dt = Local.with_ymd_and_hms(naive_datetime.year(), naive_datetime.month(), naive_datetime.day(), naive_datetime.hour()+1, 0, 0).unwrap();
-			local_dt
+			Local.with_ymd_and_hms(naive_datetime.year(), naive_datetime.month(), naive_datetime.day(), naive_datetime.hour()+1, 0, 0).unwrap()
 		})
 }
 
@@ -55,6 +54,86 @@ pub fn unixtimestamp_to_systemtime(unixtimestamp: u64) -> SystemTime {
 	}
 }
 
+/// describes `date` relative to `today`: "today", "yesterday", "tomorrow", the weekday name for the rest of the next week
+/// (e.g. "Friday"), "last" and the weekday for the rest of the past week (e.g. "last Friday"), otherwise `YYYY-MM-DD`
+pub fn format_relative_calendar(date: NaiveDate, today: NaiveDate) -> String {
+	let days = (date - today).num_days();
+	match days {
+		0 => String::from("today"),
+		-1 => String::from("yesterday"),
+		1 => String::from("tomorrow"),
+		2..=6 => date.format("%A").to_string(),
+		-6..=-2 => date.format("last %A").to_string(),
+		_ => date.format("%Y-%m-%d").to_string(),
+	}
+}
+
+/// the calendar quarter `date` falls in, 1 (Jan-Mar) to 4 (Oct-Dec)
+pub fn quarter_of_year(date: NaiveDate) -> u8 {
+	((date.month0() / 3) + 1) as u8
+}
+
+/// the first day of the calendar quarter `date` falls in, e.g. 1 April for any date in April to June
+pub fn start_of_quarter(date: NaiveDate) -> NaiveDate {
+	let first_month = (date.month0() / 3) * 3 + 1;
+	NaiveDate::from_ymd_opt(date.year(), first_month, 1).expect("The first of a month is always valid")
+}
+
+/// `dt` limited to the range `min` to `max` (inclusive): `min` if before it, `max` if after it, otherwise `dt`.
+/// Unlike `Ord::clamp` this does not panic when `min` is after `max`; the result is then always `min`
+pub fn clamp_datetime<Tz: TimeZone>(dt: DateTime<Tz>, min: DateTime<Tz>, max: DateTime<Tz>) -> DateTime<Tz> {
+	let dt = if dt > max { max } else { dt };
+	if dt < min { min } else { dt }
+}
+
+/// local midnight at the start of the day `dt` falls on, re-localized so the offset is correct on DST transition days
+pub fn truncate_to_date(dt: DateTime<Local>) -> DateTime<Local> {
+	naivedate_to_local(dt.date_naive())
+}
+
+/// time from `past` to now, negative if `past` is in the future
+pub fn elapsed_since(past: DateTime<Utc>) -> chrono::Duration {
+	elapsed_since_at(past, Utc::now())
+}
+
+/// as elapsed_since, for a local datetime
+pub fn elapsed_since_local(past: DateTime<Local>) -> chrono::Duration {
+	elapsed_since_at(past, Local::now())
+}
+
+/// as elapsed_since, with the current time given as `now`, e.g. for tests
+pub fn elapsed_since_at<Tz: TimeZone>(past: DateTime<Tz>, now: DateTime<Tz>) -> chrono::Duration {
+	now.signed_duration_since(past)
+}
+
+/// time from `now` until `future`, e.g. for a countdown. Zero when `future` is `now`, None once it has passed
+pub fn time_until(future: DateTime<Utc>, now: DateTime<Utc>) -> Option<chrono::Duration> {
+	let remaining = future.signed_duration_since(now);
+	if remaining < chrono::Duration::zero() { None } else { Some(remaining) }
+}
+
+/// which week of its month `date` falls in, 1 being the (possibly partial) week containing the 1st and weeks starting on
+/// `week_start`. A month has 4 to 6 weeks, one per row of a calendar view
+pub fn week_of_month(date: NaiveDate, week_start: Weekday) -> u8 {
+	let first_of_month = date.with_day(1).expect("The first of a month is always valid");
+	let days_before_first = first_of_month.weekday().days_since(week_start);
+	((days_before_first + date.day0()) / 7 + 1) as u8
+}
+
+/// formats tried by parse_flexible_date, in priority order
+const FLEXIBLE_DATE_FORMATS: [&str; 5] = ["%Y-%m-%d", "%d/%m/%Y", "%m/%d/%Y", "%Y%m%d", "%d %b %Y"];
+
+/// parses a date in the first of these formats that fits: `2025-06-01`, `01/06/2025` (day first), `06/13/2025`
+/// (month first), `20250601` or `1 Jun 2025`. Slashed dates are ambiguous when both parts are 12 or less, and are then
+/// read day first, so `03/04/2025` is 3 April; month first is only used when the day-first reading is invalid.
+/// Surrounding whitespace is ignored. None if no format fits
+pub fn parse_flexible_date(s: &str) -> Option<NaiveDate> {
+	let s = s.trim();
+	FLEXIBLE_DATE_FORMATS
+		.iter()
+		.find_map(|format| NaiveDate::parse_from_str(s, format).ok())
+}
+
 #[cfg(test)]
 mod tests {
     //https://docs.rs/chrono/latest/chrono/format/strftime/index.html
@@ -89,6 +168,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::zero_prefixed_literal)]
     fn test_naivedatetime_to_local_spring_forward() {
 		//in NZT, 2025-09-28 02:30 doesn't exist (clocks jump from 01:59 -> 03:00). Should return next real time, 03:00
 		// last Sunday in September, 2025-09-28 02:00
@@ -129,4 +209,166 @@ mod tests {
 		assert_eq!(result, expected);
     }
 
+    #[test]
+    fn test_format_relative_calendar() {
+		// a Wednesday
+		let today = NaiveDate::from_ymd_opt(2025, 11, 12).unwrap();
+		let relative = |days: i64| format_relative_calendar(today + chrono::Duration::days(days), today);
+		assert_eq!(relative(0), "today");
+		assert_eq!(relative(-1), "yesterday");
+		assert_eq!(relative(1), "tomorrow");
+		assert_eq!(relative(2), "Friday");
+		assert_eq!(relative(6), "Tuesday");
+		assert_eq!(relative(7), "2025-11-19");
+		assert_eq!(relative(-2), "last Monday");
+		assert_eq!(relative(-6), "last Thursday");
+		assert_eq!(relative(-7), "2025-11-05");
+    }
+
+    #[test]
+    fn test_quarter_of_year() {
+		let date = |month: u32, day: u32| NaiveDate::from_ymd_opt(2025, month, day).unwrap();
+		assert_eq!(quarter_of_year(date(1, 1)), 1);
+		assert_eq!(quarter_of_year(date(3, 31)), 1);
+		assert_eq!(quarter_of_year(date(4, 1)), 2);
+		assert_eq!(quarter_of_year(date(9, 30)), 3);
+		assert_eq!(quarter_of_year(date(12, 31)), 4);
+    }
+
+    #[test]
+    fn test_start_of_quarter() {
+		let date = |month: u32, day: u32| NaiveDate::from_ymd_opt(2025, month, day).unwrap();
+		assert_eq!(start_of_quarter(date(1, 1)), date(1, 1));
+		assert_eq!(start_of_quarter(date(3, 31)), date(1, 1));
+		assert_eq!(start_of_quarter(date(4, 1)), date(4, 1));
+		assert_eq!(start_of_quarter(date(12, 31)), date(10, 1));
+    }
+
+    #[test]
+    fn test_clamp_datetime() {
+		let min = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+		let max = Utc.with_ymd_and_hms(2025, 12, 31, 23, 59, 59).unwrap();
+		let below = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+		let within = Utc.with_ymd_and_hms(2025, 6, 1, 12, 0, 0).unwrap();
+		let above = Utc.with_ymd_and_hms(2026, 6, 1, 12, 0, 0).unwrap();
+		assert_eq!(clamp_datetime(below, min, max), min);
+		assert_eq!(clamp_datetime(within, min, max), within);
+		assert_eq!(clamp_datetime(above, min, max), max);
+		assert_eq!(clamp_datetime(max, min, max), max);
+    }
+
+    #[test]
+    fn test_clamp_datetime_min_after_max() {
+		let min = Utc.with_ymd_and_hms(2025, 12, 31, 0, 0, 0).unwrap();
+		let max = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+		let within = Utc.with_ymd_and_hms(2025, 6, 1, 0, 0, 0).unwrap();
+		assert_eq!(clamp_datetime(within, min, max), min);
+		assert_eq!(clamp_datetime(max, min, max), min);
+    }
+
+    #[test]
+    fn test_truncate_to_date() {
+		let dt = Local.with_ymd_and_hms(2025, 11, 15, 13, 45, 10).unwrap();
+		let expected = Local.with_ymd_and_hms(2025, 11, 15, 0, 0, 0).unwrap();
+		assert_eq!(truncate_to_date(dt), expected);
+		assert_eq!(truncate_to_date(expected), expected);
+    }
+
+    #[test]
+    fn test_truncate_to_date_dst_day() {
+		// the first day this year on which the local offset changes between midnight and late evening, if the local
+		// timezone has daylight saving at all
+		let evening_offset = |date: NaiveDate| Local.from_local_datetime(&date.and_hms_opt(23, 0, 0).unwrap()).earliest().map(|dt| *dt.offset());
+		let dst_day = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap().iter_days().take(365)
+			.find(|&date| evening_offset(date) != Some(*naivedate_to_local(date).offset()));
+		if let Some(date) = dst_day {
+			let dt = Local.from_local_datetime(&date.and_hms_opt(23, 0, 0).unwrap()).earliest().unwrap();
+			let result = truncate_to_date(dt);
+			assert_eq!(result.naive_local(), date.and_time(NaiveTime::MIN));
+			assert_eq!(*result.offset(), Local.offset_from_local_datetime(&result.naive_local()).unwrap());
+			assert_ne!(result.offset(), dt.offset());
+		}
+    }
+
+    #[test]
+    fn test_elapsed_since_at() {
+		let now = Utc.with_ymd_and_hms(2025, 6, 1, 12, 0, 0).unwrap();
+		let past = Utc.with_ymd_and_hms(2025, 6, 1, 10, 30, 0).unwrap();
+		assert_eq!(elapsed_since_at(past, now), chrono::Duration::minutes(90));
+		assert_eq!(elapsed_since_at(now, now), chrono::Duration::zero());
+		assert_eq!(elapsed_since_at(now, past), chrono::Duration::minutes(-90));
+    }
+
+    #[test]
+    fn test_elapsed_since_at_offset_change() {
+		// the clocks went forward an hour at 2am on 2025-09-28 in New Zealand, from +12:00 to +13:00
+		let nzst = chrono::FixedOffset::east_opt(12 * 3600).unwrap();
+		let nzdt = chrono::FixedOffset::east_opt(13 * 3600).unwrap();
+		let past = nzst.with_ymd_and_hms(2025, 9, 28, 1, 0, 0).unwrap();
+		let now = nzdt.with_ymd_and_hms(2025, 9, 28, 4, 0, 0).unwrap();
+		assert_eq!(elapsed_since_at(past, now.with_timezone(&nzst)), chrono::Duration::hours(2));
+    }
+
+    #[test]
+    fn test_elapsed_since() {
+		let past = Utc::now() - chrono::Duration::seconds(5);
+		assert!(elapsed_since(past) >= chrono::Duration::seconds(5));
+		assert!(elapsed_since_local(Local::now()) >= chrono::Duration::zero());
+    }
+
+    #[test]
+    fn test_time_until() {
+		let now = Utc.with_ymd_and_hms(2025, 6, 1, 12, 0, 0).unwrap();
+		let future = Utc.with_ymd_and_hms(2025, 6, 2, 12, 0, 30).unwrap();
+		assert_eq!(time_until(future, now), Some(chrono::Duration::seconds(86_430)));
+		assert_eq!(time_until(now, now), Some(chrono::Duration::zero()));
+		assert_eq!(time_until(now - chrono::Duration::milliseconds(1), now), None);
+    }
+
+    #[test]
+    fn test_week_of_month() {
+		// 2025-06-01 is a Sunday
+		let first = NaiveDate::from_ymd_opt(2025, 6, 1).unwrap();
+		assert_eq!(week_of_month(first, Weekday::Mon), 1);
+		assert_eq!(week_of_month(first, Weekday::Sun), 1);
+		let second_monday = NaiveDate::from_ymd_opt(2025, 6, 2).unwrap();
+		assert_eq!(week_of_month(second_monday, Weekday::Mon), 2);
+		assert_eq!(week_of_month(second_monday, Weekday::Sun), 1);
+    }
+
+    #[test]
+    fn test_week_of_month_last_week() {
+		let last = NaiveDate::from_ymd_opt(2025, 6, 30).unwrap();
+		assert_eq!(week_of_month(last, Weekday::Mon), 6);
+		assert_eq!(week_of_month(last, Weekday::Sun), 5);
+		// February 2021 starts on a Monday and fits exactly four Monday-start weeks
+		let february_end = NaiveDate::from_ymd_opt(2021, 2, 28).unwrap();
+		assert_eq!(week_of_month(february_end, Weekday::Mon), 4);
+    }
+
+    #[test]
+    fn test_parse_flexible_date() {
+		let expected = NaiveDate::from_ymd_opt(2025, 6, 1);
+		assert_eq!(parse_flexible_date("2025-06-01"), expected);
+		assert_eq!(parse_flexible_date("01/06/2025"), expected);
+		assert_eq!(parse_flexible_date("20250601"), expected);
+		assert_eq!(parse_flexible_date("1 Jun 2025"), expected);
+		assert_eq!(parse_flexible_date(" 01 jun 2025\n"), expected);
+    }
+
+    #[test]
+    fn test_parse_flexible_date_day_month_order() {
+		// day first wins when both readings are valid
+		assert_eq!(parse_flexible_date("03/04/2025"), NaiveDate::from_ymd_opt(2025, 4, 3));
+		// 13 can't be a month, so this is month first
+		assert_eq!(parse_flexible_date("06/13/2025"), NaiveDate::from_ymd_opt(2025, 6, 13));
+    }
+
+    #[test]
+    fn test_parse_flexible_date_unparseable() {
+		assert_eq!(parse_flexible_date("not a date"), None);
+		assert_eq!(parse_flexible_date("2025-02-30"), None);
+		assert_eq!(parse_flexible_date(""), None);
+    }
+
 }
@@ -0,0 +1,170 @@
+use std::{
+    error::Error as StdError,
+    fmt,
+    io,
+};
+
+/// The error returned by the helper_lib functions that can fail in more than one way, so callers can match on the cause.
+/// Functions with a single source of failure (e.g. most of sql returning `rusqlite::Error`) keep that type; it converts
+/// into HelperError with `?`
+#[derive(Debug)]
+pub enum HelperError {
+    /// sqlite error from rusqlite
+    #[cfg(feature = "sql")]
+    Sql(rusqlite::Error),
+    /// no pooled connection could be opened within the pool's timeout
+    #[cfg(feature = "pool")]
+    Pool(r2d2::Error),
+    /// the clipboard could not be opened or written
+    #[cfg(feature = "clipboard")]
+    Clipboard(arboard::Error),
+    /// reading or writing a file or stream
+    Io(io::Error),
+    /// text that could not be parsed, e.g. a bad format description
+    Parse(String),
+    /// a logger has already been set for this process
+    Logger(log::SetLoggerError),
+    /// arguments that don't fit together, e.g. a different number of columns and values
+    InvalidInput(String),
+}
+
+impl fmt::Display for HelperError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "sql")]
+            HelperError::Sql(e) => write!(f, "sql error: {}", e),
+            #[cfg(feature = "pool")]
+            HelperError::Pool(e) => write!(f, "pool error: {}", e),
+            #[cfg(feature = "clipboard")]
+            HelperError::Clipboard(e) => write!(f, "clipboard error: {}", e),
+            HelperError::Io(e) => write!(f, "io error: {}", e),
+            HelperError::Parse(message) => write!(f, "parse error: {}", message),
+            HelperError::Logger(e) => write!(f, "logger error: {}", e),
+            HelperError::InvalidInput(message) => write!(f, "invalid input: {}", message),
+        }
+    }
+}
+
+impl StdError for HelperError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            #[cfg(feature = "sql")]
+            HelperError::Sql(e) => Some(e),
+            #[cfg(feature = "pool")]
+            HelperError::Pool(e) => Some(e),
+            #[cfg(feature = "clipboard")]
+            HelperError::Clipboard(e) => Some(e),
+            HelperError::Io(e) => Some(e),
+            HelperError::Logger(e) => Some(e),
+            HelperError::Parse(_) | HelperError::InvalidInput(_) => None,
+        }
+    }
+}
+
+#[cfg(feature = "sql")]
+impl From<rusqlite::Error> for HelperError {
+    fn from(e: rusqlite::Error) -> Self {
+        HelperError::Sql(e)
+    }
+}
+
+#[cfg(feature = "pool")]
+impl From<r2d2::Error> for HelperError {
+    fn from(e: r2d2::Error) -> Self {
+        HelperError::Pool(e)
+    }
+}
+
+#[cfg(feature = "clipboard")]
+impl From<arboard::Error> for HelperError {
+    fn from(e: arboard::Error) -> Self {
+        HelperError::Clipboard(e)
+    }
+}
+
+impl From<io::Error> for HelperError {
+    fn from(e: io::Error) -> Self {
+        HelperError::Io(e)
+    }
+}
+
+impl From<log::SetLoggerError> for HelperError {
+    fn from(e: log::SetLoggerError) -> Self {
+        HelperError::Logger(e)
+    }
+}
+
+impl From<time::error::InvalidFormatDescription> for HelperError {
+    fn from(e: time::error::InvalidFormatDescription) -> Self {
+        HelperError::Parse(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "sql")]
+    #[test]
+    fn test_helper_error_sql() {
+        let e: HelperError = rusqlite::Error::QueryReturnedNoRows.into();
+        assert!(matches!(e, HelperError::Sql(rusqlite::Error::QueryReturnedNoRows)));
+        assert!(e.source().is_some());
+    }
+
+    #[cfg(feature = "pool")]
+    #[test]
+    fn test_helper_error_pool() {
+        let manager = r2d2_sqlite::SqliteConnectionManager::file("/nonexistent_dir/helper_lib_pool.db");
+        let e: HelperError = r2d2::Pool::builder()
+            .connection_timeout(std::time::Duration::from_millis(100))
+            .build(manager)
+            .unwrap_err()
+            .into();
+        assert!(matches!(e, HelperError::Pool(_)));
+        assert!(e.to_string().starts_with("pool error: "));
+    }
+
+    #[cfg(feature = "clipboard")]
+    #[test]
+    fn test_helper_error_clipboard() {
+        let e: HelperError = arboard::Error::ContentNotAvailable.into();
+        assert!(matches!(e, HelperError::Clipboard(arboard::Error::ContentNotAvailable)));
+        assert!(e.to_string().starts_with("clipboard error: "));
+    }
+
+    #[test]
+    fn test_helper_error_io() {
+        let e: HelperError = io::Error::new(io::ErrorKind::NotFound, "no such file").into();
+        assert!(matches!(&e, HelperError::Io(io_error) if io_error.kind() == io::ErrorKind::NotFound));
+        assert_eq!(e.to_string(), "io error: no such file");
+    }
+
+    #[test]
+    fn test_helper_error_parse() {
+        let e: HelperError = time::format_description::parse_borrowed::<1>("[hour").unwrap_err().into();
+        assert!(matches!(e, HelperError::Parse(_)));
+        assert!(e.source().is_none());
+    }
+
+    #[test]
+    fn test_helper_error_logger() {
+        struct NopLogger;
+        impl log::Log for NopLogger {
+            fn enabled(&self, _: &log::Metadata) -> bool { false }
+            fn log(&self, _: &log::Record) {}
+            fn flush(&self) {}
+        }
+        static LOGGER: NopLogger = NopLogger;
+        //the second call fails whether or not the first one set the logger
+        _ = log::set_logger(&LOGGER);
+        let e: HelperError = log::set_logger(&LOGGER).unwrap_err().into();
+        assert!(matches!(e, HelperError::Logger(_)));
+    }
+
+    #[test]
+    fn test_helper_error_invalid_input() {
+        let e = HelperError::InvalidInput(String::from("2 columns but 3 values"));
+        assert_eq!(e.to_string(), "invalid input: 2 columns but 3 values");
+    }
+}
@@ -0,0 +1,65 @@
+use crate::hex::to_hex;
+use sha2::{Digest, Sha256};
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+/// the sha256 digest of the file at `path` as lowercase hex. The file is read in 64KB chunks, so it is never all in memory
+pub fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(len) => hasher.update(&buffer[..len]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(to_hex(&hasher.finalize()))
+}
+
+/// the sha256 digest of `bytes` as lowercase hex
+pub fn sha256_bytes(bytes: &[u8]) -> String {
+    to_hex(&Sha256::digest(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ABC_SHA256: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    #[test]
+    fn test_sha256_file_fixture() {
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/abc.txt");
+        assert_eq!(sha256_file(&fixture).unwrap(), ABC_SHA256);
+    }
+
+    #[test]
+    fn test_sha256_file_larger_than_chunk() {
+        //spans several reads, so checks the chunks are hashed in order
+        let bytes: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        let path = std::env::temp_dir().join("helper_lib_test_sha256_file_larger_than_chunk.bin");
+        std::fs::write(&path, &bytes).unwrap();
+        let digest = sha256_file(&path);
+        //clean up temp file
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(digest.unwrap(), sha256_bytes(&bytes));
+    }
+
+    #[test]
+    fn test_sha256_file_missing() {
+        let path = std::env::temp_dir().join("helper_lib_test_sha256_file_missing.bin");
+        assert_eq!(sha256_file(&path).unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_sha256_bytes() {
+        assert_eq!(sha256_bytes(b"abc"), ABC_SHA256);
+        assert_eq!(sha256_bytes(b""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+    }
+}
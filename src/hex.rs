@@ -0,0 +1,31 @@
+pub use ::hex::FromHexError;
+
+/// encodes `bytes` as lowercase hex, two digits per byte, as sql::query_to_string does for BLOBs
+pub fn to_hex(bytes: &[u8]) -> String {
+    ::hex::encode(bytes)
+}
+
+/// decodes hex digits (either case) back to bytes. Errors on an odd number of digits or a non-hex character
+pub fn from_hex(s: &str) -> Result<Vec<u8>, FromHexError> {
+    ::hex::decode(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_hex_from_hex_round_trip() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        assert_eq!(from_hex(&to_hex(&bytes)).unwrap(), bytes);
+        assert_eq!(to_hex(&[0xde, 0xad, 0xbe, 0xef]), "deadbeef");
+        assert_eq!(from_hex("DEADbeef").unwrap(), vec![0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(to_hex(&[]), "");
+    }
+
+    #[test]
+    fn test_from_hex_errors() {
+        assert_eq!(from_hex("abc"), Err(FromHexError::OddLength));
+        assert_eq!(from_hex("zz"), Err(FromHexError::InvalidHexCharacter { c: 'z', index: 0 }));
+    }
+}
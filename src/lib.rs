@@ -1,148 +1,148 @@
-#[cfg(target_os = "windows")]
-use crossterm::event::{self, Event, KeyCode};
+//! Helper functions shared between my projects. `strings`, `paths`, `retry` and the logger setup are always built; the other
+//! modules are behind Cargo features so only the dependencies in use get compiled:
+//!
+//! - `datetime` (default): the datetime module (chrono)
+//! - `hashing`: the hashing module of sha256 digests (sha2)
+//! - `hex`: the hex module (hex)
+//! - `regex` (default): the regex module (regex)
+//! - `sql`: the sql module (rusqlite, chrono), plus `json` for sql::query_to_json and `uuid` for dbfmt of uuid::Uuid, `async` for the tokio wrappers such as sql::query_to_tuples_async and `pool` for sql::SqlitePool
+//! - `base64`: the base64 module (base64)
+//! - `clipboard`: the clipboard module (arboard)
+//! - `ctrlc`: watch_for_shutdown, stopping on a termination signal (ctrlc)
+//! - `tui`: the tui module of key watchers and terminal settings (termios / crossterm), re-exported at the crate root
+//!
+//! `full` turns on all of them. Use `default-features = false` for just `strings` and `paths`.
+
 use log::*;
 use simplelog::*;
 use std::{
-    sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
-    },
-};
-#[cfg(target_os = "linux")]
-use std::{
-    io::{self, Read, Write},
-    sync::mpsc::{self, Sender},
-    thread,
-    time::{Duration},
+    fs::OpenOptions,
+    path::Path,
+    sync::Once,
 };
-#[cfg(target_os = "linux")]
-use termios::{Termios, TCSANOW, ECHO, ICANON, tcsetattr};
+#[cfg(feature = "ctrlc")]
+use std::sync::{atomic::{AtomicBool, Ordering}, Arc};
 
+#[cfg(feature = "base64")]
+pub mod base64;
+#[cfg(feature = "clipboard")]
 pub mod clipboard;
+#[cfg(feature = "datetime")]
 pub mod datetime;
+pub mod error;
+#[cfg(feature = "hashing")]
+pub mod hashing;
+#[cfg(feature = "hex")]
+pub mod hex;
 pub mod paths;
+#[cfg(feature = "regex")]
 pub mod regex;
+pub mod retry;
+#[cfg(feature = "sql")]
 pub mod sql;
 pub mod strings;
+#[cfg(feature = "tui")]
+pub mod tui;
 
-pub fn setup_logger(level_filter: LevelFilter) {
-	let logger_config = ConfigBuilder::new()
-		.set_time_offset_to_local().expect("Failed to get local time offset")
+pub use error::HelperError;
+#[cfg(feature = "tui")]
+pub use tui::*;
+
+/// logger config shared by the setup_logger functions: `hh:mm:ss.sss` timestamps in local time.
+/// The local offset can't be read safely once other threads are running (e.g. under the test harness), in which case UTC is used
+fn logger_config() -> Config {
+	let mut builder = ConfigBuilder::new();
+	// on failure the offset is left at UTC
+	_ = builder.set_time_offset_to_local();
+	builder
 		.set_time_format_custom(format_description!("[hour]:[minute]:[second].[subsecond digits:3]"))
-		.build();
+		.build()
+}
+
+fn init_term_logger(level_filter: LevelFilter) -> Result<(), SetLoggerError> {
+	let logger_config = logger_config();
 	CombinedLogger::init(
 		vec![
 			TermLogger::new(level_filter, logger_config, TerminalMode::Mixed, ColorChoice::Auto),
 			// TermLogger::new(LevelFilter::Debug, Config::default(), TerminalMode::Mixed, ColorChoice::Auto),
-			// WriteLogger::new(LevelFilter::Error, Config::default(), File::create("my_rust_binary.log").unwrap()),
 		]
-	).unwrap();
+	)
 }
 
-pub fn watch_for_quit(keep_going: Arc<AtomicBool>) {
-    #[cfg(target_os = "windows")]
-    {
-        while keep_going.load(Ordering::Relaxed) {
-            // event::read() is blocking and waits for the next event
-            match event::read() {
-                Ok(Event::Key(key_event)) => {
-                    if key_event.code == KeyCode::Char('q') {
-                        println!("Quit key 'q' pressed.");
-                        break; // Exit the input thread loop
-                    }
-                },
-                Ok(_) => {
-                    // Ignore other events (like mouse or resize)
-                },
-                Err(e) => {
-                    eprintln!("\nInput thread error: {}. Shutting down.", e);
-                    break;
-                }
-            }
-        }
-    }
-    #[cfg(target_os = "linux")]
-    {
-        let stdin = 0;
-        let termios = Termios::from_fd(stdin).unwrap();
-        let mut new_termios = termios.clone();  // make a mutable copy of termios that we will modify
-        new_termios.c_lflag &= !(ICANON | ECHO); // no echo and canonical mode
-        tcsetattr(stdin, TCSANOW, &mut new_termios).unwrap();
-
-        let (tx, rx) = mpsc::channel::<u8>();
-        // Spawn the key_press_watcher_linux thread, passing the sender (tx) into it.
-        _ = thread::spawn(move || {key_press_watcher_linux(tx);});
+/// sets up terminal logging. Panics if a logger has already been set, see setup_logger_once
+pub fn setup_logger(level_filter: LevelFilter) {
+	init_term_logger(level_filter).unwrap();
+}
 
-        //poll Q
-        let mut key_seq:Vec<u8> = Vec::new();
-        while keep_going.load(Ordering::Relaxed) {
-            match rx.try_recv() {
-                // Case 1: A byte was successfully received.
-                Ok(byte) => {
-                    //this picks up all bytes in the queue and stored them in key_seq at once, so there is no need to check for time between ESC and other codes
-                    // println!("[Consumer] Read byte: {}", byte);
-                    key_seq.push(byte);
-                }
-                // Case 2: The queue is currently empty (No message available).
-                Err(mpsc::TryRecvError::Empty) => {
-                    // no keypress byte to process
-                    if !key_seq.is_empty(){
-                        if key_seq[0] == 113 || key_seq[0] == 81 {
-                            //Q or q key pressed
-                            println!("Quit key 'q' pressed.");
-                            break;
-                        }
-                        //println!("key_seq: {:?}", key_seq);
-                        key_seq.clear();
-                    }
+/// as setup_logger, but only the first call in the process sets up the logger; later calls (and a call after some other
+/// logger has been set) are silent no-ops, so several tests or components can each call it without coordinating
+pub fn setup_logger_once(level_filter: LevelFilter) {
+	static LOGGER_INIT: Once = Once::new();
+	LOGGER_INIT.call_once(|| {
+		_ = init_term_logger(level_filter);
+	});
+}
 
-                }
-                // Case 3: The sender (producer thread) has hung up or panicked.
-                Err(mpsc::TryRecvError::Disconnected) => {
-                    println!("get_key_sequence(): Sender disconnected");
-                    tcsetattr(stdin, TCSANOW, & termios).unwrap();  // reset the stdin to original termios data
-                    break;
-                }
-            }
-            
-            thread::sleep(Duration::from_millis(20));
-        }
+/// as setup_logger, but also appends log lines at `file_level_filter` and above to the file at `path` (created if missing).
+/// Errors if the file can't be opened or a logger has already been set
+pub fn setup_logger_with_file(level_filter: LevelFilter, file_level_filter: LevelFilter, path: &Path) -> Result<(), HelperError> {
+	let log_file = OpenOptions::new().create(true).append(true).open(path)?;
+	CombinedLogger::init(
+		vec![
+			TermLogger::new(level_filter, logger_config(), TerminalMode::Mixed, ColorChoice::Auto),
+			WriteLogger::new(file_level_filter, logger_config(), log_file),
+		]
+	)?;
+	Ok(())
+}
 
-        tcsetattr(stdin, TCSANOW, & termios).unwrap();  // reset the stdin to original termios data
-    }
-    #[cfg(not(any(target_os = "linux", target_os = "windows")))]
-    {
-        keep_going.store(false, Ordering::Relaxed);
-        panic!("Key watching not coded for this OS");
-    }
+/// as setup_logger, but with the timestamp in `time_format` (a `time` crate format description, e.g. `"[year]-[month]-[day] [hour]:[minute]:[second]"`),
+/// in local time or UTC. Errors if the format is invalid or a logger has already been set
+pub fn setup_logger_custom(level_filter: LevelFilter, time_format: &str, use_local_time: bool) -> Result<(), HelperError> {
+	setup_logger_custom_colors(level_filter, time_format, use_local_time, &[])
+}
 
-    keep_going.store(false, Ordering::Relaxed);
+/// as setup_logger_custom, but also overrides the terminal color of each level in `level_colors`, None for no color
+pub fn setup_logger_custom_colors(level_filter: LevelFilter, time_format: &str, use_local_time: bool, level_colors: &[(Level, Option<Color>)]) -> Result<(), HelperError> {
+	// a bad format errors here, before anything is leaked
+	time::format_description::parse_borrowed::<1>(time_format)?;
+	// simplelog keeps the format for the life of the logger, which is the life of the process, so it needs 'static
+	// references. They are leaked here and reclaimed below if the logger can't be set after all
+	let time_format: *mut str = Box::into_raw(time_format.to_owned().into_boxed_str());
+	// SAFETY: time_format was just allocated and is only freed below, once nothing borrows it
+	let format_items = time::format_description::parse_borrowed::<1>(unsafe { &*time_format })?;
+	let format_items = Box::into_raw(format_items.into_boxed_slice());
+	let mut builder = ConfigBuilder::new();
+	if use_local_time {
+		// on failure the offset is left at UTC
+		_ = builder.set_time_offset_to_local();
+	}
+	for &(level, color) in level_colors {
+		builder.set_level_color(level, color);
+	}
+	// SAFETY: as time_format, format_items is only freed below once nothing borrows it
+	let logger_config = builder.set_time_format_custom(unsafe { &*format_items }).build();
+	let result = CombinedLogger::init(
+		vec![
+			TermLogger::new(level_filter, logger_config, TerminalMode::Mixed, ColorChoice::Auto),
+		]
+	);
+	if result.is_err() {
+		// SAFETY: a failed init drops the logger, and with it the config's borrows of the format
+		unsafe {
+			drop(Box::from_raw(format_items));
+			drop(Box::from_raw(time_format));
+		}
+	}
+	result?;
+	Ok(())
 }
 
-#[cfg(target_os = "linux")]
-fn key_press_watcher_linux(tx: Sender<u8>) {
-    let stdout = io::stdout();
-    let mut reader = io::stdin();
-    let mut buffer = [0;1];  // read exactly one byte
-    stdout.lock().flush().unwrap();
-    loop {
-        //reader.read_exact(&mut buffer).unwrap();
-        match reader.read(&mut buffer) {
-            Ok(_) => {
-                //println!("len_of_buffer: {}", len_of_buffer);
-            }
-            Err(e) => {
-                println!("key_press_watcher_linux reader err: {}", e);
-            }
-        }
-        match tx.send(buffer[0]) {
-            Ok(_) => {
-                //println!("send success");
-            }
-            Err(_) => {
-                //println!("send error: {:?}", e);
-                return;
-            }
-        }
-    }
+/// sets `keep_going` to false when the process gets SIGINT or SIGTERM (Ctrl-C, or a close/shutdown event on Windows),
+/// instead of the process being killed. To also stop on the quit key, pass a clone of the same flag to `watch_for_quit`
+/// or `spawn_quit_watcher` (tui feature) so the app watches one flag whichever way shutdown is asked for.
+/// Errors if a signal handler has already been set for this process
+#[cfg(feature = "ctrlc")]
+pub fn watch_for_shutdown(keep_going: Arc<AtomicBool>) -> Result<(), ctrlc::Error> {
+	ctrlc::set_handler(move || keep_going.store(false, Ordering::SeqCst))
 }
@@ -1,20 +1,36 @@
-﻿use std::path::{Component, Path, PathBuf};
+﻿use std::{
+	collections::HashSet,
+	fs::{self, File},
+	io::{self, Write},
+	path::{Component, Path, PathBuf},
+	sync::atomic::{AtomicU64, Ordering},
+};
 
 pub fn format_bytes(bytes:u64) -> String {
 	if bytes < 1_024 {
-		return format!("{}B", bytes)
+		format!("{}B", bytes)
 	}
 	else if bytes < 1_048_576 {
-		return format!("{:.1}KB", bytes as f64 / 1024.0)
+		format!("{:.1}KB", bytes as f64 / 1024.0)
 	}
 	else if bytes < 1_073_741_824 {
-		return format!("{:.1}MB", bytes as f64 / 1_048_576.0)
+		format!("{:.1}MB", bytes as f64 / 1_048_576.0)
 	}
 	else if bytes < 1_099_511_627_776 {
-		return format!("{:.1}GB", bytes as f64 / 1_073_741_824.0)
+		format!("{:.1}GB", bytes as f64 / 1_073_741_824.0)
 	}
 	else {
-		return format!("{:.1}TB", bytes as f64 / 1_099_511_627_776.0)
+		format!("{:.1}TB", bytes as f64 / 1_099_511_627_776.0)
+	}
+}
+
+/// as format_bytes, for a change in size: `+2.3MB`, `-512B`, or `0B` for no change
+pub fn format_bytes_delta(delta:i64) -> String {
+	let magnitude = format_bytes(delta.unsigned_abs());
+	match delta {
+		0 => magnitude,
+		d if d < 0 => format!("-{}", magnitude),
+		_ => format!("+{}", magnitude),
 	}
 }
 
@@ -33,11 +49,8 @@ pub fn path_to_agnostic_relative(path: &Path, base: &Path) -> String {
 			if !rtn.is_empty() {
 				sep = "/";
 			}
-			match path_component {
-				Component::Normal(c) => {
-					rtn.push_str(&format!("{}{}", sep, c.to_string_lossy()));
-				},
-				_ => {},
+			if let Component::Normal(c) = path_component {
+				rtn.push_str(&format!("{}{}", sep, c.to_string_lossy()));
 			}
 		}
 
@@ -45,13 +58,13 @@ pub fn path_to_agnostic_relative(path: &Path, base: &Path) -> String {
 	}
 
 	
-	return rtn;
+	rtn
 }
 
 
 /// a function to append an extension
 /// as at writing this, `PathBuf::add_extension` fn is blocked as unstable
-/// 	https://github.com/rust-lang/rust/issues/127292
+///     https://github.com/rust-lang/rust/issues/127292
 pub fn add_extension(path:&Path, extension:&str) -> PathBuf {
 	let mut out_pathbuf = PathBuf::new();
 	// get components
@@ -71,6 +84,257 @@ pub fn add_extension(path:&Path, extension:&str) -> PathBuf {
 	out_pathbuf
 }
 
+/// writes `contents` to `path` so that, even after a crash or power cut, `path` holds either its old contents or all of the new.
+/// The contents go to a temp file next to `path` (`<name>.<pid>.<n>.tmp`, `n` counting calls in this process so concurrent
+/// writers never share a temp file), which is fsynced and then renamed over `path`.
+/// The rename is only atomic within one filesystem, which is why the temp file is made in the same directory rather than
+/// the system temp dir. On error the temp file is removed and `path` is left as it was
+pub fn write_atomic(path:&Path, contents:&[u8]) -> io::Result<()> {
+	static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+	let n = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+	let temp_path = add_extension(path, &format!("{}.{}.tmp", std::process::id(), n));
+	let result = write_and_rename(&temp_path, path, contents);
+	if result.is_err() {
+		_ = fs::remove_file(&temp_path);
+	}
+	result
+}
+
+fn write_and_rename(temp_path:&Path, path:&Path, contents:&[u8]) -> io::Result<()> {
+	let mut temp_file = File::create(temp_path)?;
+	temp_file.write_all(contents)?;
+	temp_file.sync_all()?;
+	drop(temp_file);
+	fs::rename(temp_path, path)?;
+	// the rename itself is only durable once the directory is synced. Windows can't open a directory as a file
+	#[cfg(unix)]
+	{
+		let parent = match path.parent() {
+			Some(parent) if !parent.as_os_str().is_empty() => parent,
+			_ => Path::new("."),
+		};
+		File::open(parent)?.sync_all()?;
+	}
+	Ok(())
+}
+
+/// true for a dot file or directory (name starting with `.`, other than `.` and `..` themselves), and on Windows also
+/// for anything with the hidden attribute
+pub fn is_hidden(path:&Path) -> bool {
+	let dot_name = path.file_name()
+		.map(|name| name.to_string_lossy().starts_with('.'))
+		.unwrap_or(false);
+	#[cfg(target_os = "windows")]
+	{
+		use std::os::windows::fs::MetadataExt;
+		const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+		if let Ok(metadata) = fs::symlink_metadata(path)
+			&& metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0 {
+			return true;
+		}
+	}
+	dot_name
+}
+
+/// recursively yields every regular file under `root`, directories themselves are not yielded. The order is unspecified.
+/// Symlinks (to files or directories) are only followed with `follow_symlinks`, and a directory reached twice is walked once,
+/// so symlink loops end. Hidden files and directories (see is_hidden) are skipped unless `include_hidden`.
+/// Entries that can't be read, e.g. for lack of permission, are skipped
+pub fn walk_files(root:&Path, follow_symlinks:bool, include_hidden:bool) -> impl Iterator<Item = PathBuf> {
+	WalkFiles {
+		pending_dirs: vec![root.to_path_buf()],
+		current_dir: None,
+		visited_dirs: HashSet::new(),
+		follow_symlinks,
+		include_hidden,
+	}
+}
+
+struct WalkFiles {
+	pending_dirs: Vec<PathBuf>,
+	current_dir: Option<fs::ReadDir>,
+	visited_dirs: HashSet<PathBuf>,
+	follow_symlinks: bool,
+	include_hidden: bool,
+}
+
+impl Iterator for WalkFiles {
+	type Item = PathBuf;
+
+	fn next(&mut self) -> Option<PathBuf> {
+		loop {
+			let Some(read_dir) = self.current_dir.as_mut() else {
+				let dir = self.pending_dirs.pop()?;
+				// canonicalize so the same directory through different symlinks is recognised
+				let canonical = fs::canonicalize(&dir).unwrap_or_else(|_| dir.clone());
+				if self.visited_dirs.insert(canonical) {
+					self.current_dir = fs::read_dir(&dir).ok();
+				}
+				continue;
+			};
+			let Some(entry) = read_dir.next() else {
+				self.current_dir = None;
+				continue;
+			};
+			let Ok(entry) = entry else { continue; };
+			let path = entry.path();
+			if !self.include_hidden && is_hidden(&path) {
+				continue;
+			}
+			let Ok(mut file_type) = entry.file_type() else { continue; };
+			if file_type.is_symlink() {
+				if !self.follow_symlinks {
+					continue;
+				}
+				// a broken link has nothing to follow
+				let Ok(metadata) = fs::metadata(&path) else { continue; };
+				file_type = metadata.file_type();
+			}
+			if file_type.is_dir() {
+				self.pending_dirs.push(path);
+			} else if file_type.is_file() {
+				return Some(path);
+			}
+		}
+	}
+}
+
+/// true if `child` is `ancestor` or somewhere beneath it, e.g. to keep a requested path under a server's root.
+/// Compared component by component after resolving `.` and `..` in the text of the paths, so `root/../etc` is not under
+/// `root`. The filesystem isn't read, so canonicalize first if symlinks could lead out. Case-insensitive on Windows
+pub fn is_subpath(child:&Path, ancestor:&Path) -> bool {
+	let child = lexical_components(child);
+	let ancestor = lexical_components(ancestor);
+	child.len() >= ancestor.len()
+		&& child.iter().zip(&ancestor).all(|(c, a)| components_eq(c, a))
+		// a leading `..` left over in a relative child climbs out of the ancestor
+		&& !child[ancestor.len()..].contains(&Component::ParentDir)
+}
+
+/// the components of `path` with `.` dropped and each `..` removing the component before it, where there is one
+fn lexical_components(path:&Path) -> Vec<Component<'_>> {
+	let mut components: Vec<Component> = Vec::new();
+	for component in path.components() {
+		match component {
+			Component::CurDir => {},
+			Component::ParentDir => match components.last() {
+				Some(Component::Normal(_)) => { components.pop(); },
+				// `..` at the root stays at the root
+				Some(Component::RootDir) | Some(Component::Prefix(_)) => {},
+				_ => components.push(component),
+			},
+			_ => components.push(component),
+		}
+	}
+	components
+}
+
+fn components_eq(a:&Component, b:&Component) -> bool {
+	#[cfg(target_os = "windows")]
+	{
+		a.as_os_str().to_string_lossy().to_lowercase() == b.as_os_str().to_string_lossy().to_lowercase()
+	}
+	#[cfg(not(target_os = "windows"))]
+	{
+		a == b
+	}
+}
+
+/// true if the filename `name` matches the glob `pattern`, e.g. `*.tar.gz`. `*` is any run of characters, `?` any one
+/// character and `[abc]` / `[a-z]` one of a set (`[!abc]` for none of them). Neither `*` nor `?` matches a path separator.
+/// Everything else, including an unclosed `[`, is literal
+pub fn matches_glob(name:&str, pattern:&str) -> bool {
+	let tokens = parse_glob(pattern);
+	let name: Vec<char> = name.chars().collect();
+	// matched[j] is true when the tokens from the current one onwards match name[j..], filled from the last token back
+	let mut matched = vec![false; name.len() + 1];
+	matched[name.len()] = true;
+	for token in tokens.iter().rev() {
+		let mut next = vec![false; name.len() + 1];
+		for j in (0..=name.len()).rev() {
+			next[j] = match token {
+				GlobToken::Star => matched[j] || (j < name.len() && !is_separator(name[j]) && next[j + 1]),
+				_ => j < name.len() && token.matches(name[j]) && matched[j + 1],
+			};
+		}
+		matched = next;
+	}
+	matched[0]
+}
+
+enum GlobToken {
+	Star,
+	Question,
+	Set { negated:bool, ranges:Vec<(char, char)> },
+	Literal(char),
+}
+
+impl GlobToken {
+	/// whether this single-character token matches `c`
+	fn matches(&self, c:char) -> bool {
+		match self {
+			GlobToken::Star => true,
+			GlobToken::Question => !is_separator(c),
+			GlobToken::Set { negated, ranges } => ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi) != *negated,
+			GlobToken::Literal(l) => *l == c,
+		}
+	}
+}
+
+fn is_separator(c:char) -> bool {
+	c == '/' || c == '\\'
+}
+
+fn parse_glob(pattern:&str) -> Vec<GlobToken> {
+	let chars: Vec<char> = pattern.chars().collect();
+	let mut tokens = Vec::new();
+	let mut i = 0;
+	while i < chars.len() {
+		match chars[i] {
+			'*' => tokens.push(GlobToken::Star),
+			'?' => tokens.push(GlobToken::Question),
+			'[' => {
+				// a `]` straight after the `[` (or `[!`) is part of the set
+				let negated = chars.get(i + 1) == Some(&'!');
+				let set_start = if negated { i + 2 } else { i + 1 };
+				let set_end = (set_start + 1..chars.len()).find(|&j| chars[j] == ']');
+				match set_end {
+					Some(end) => {
+						let set = &chars[set_start..end];
+						let mut ranges = Vec::new();
+						let mut k = 0;
+						while k < set.len() {
+							// a `-` at either end of the set is literal
+							if k + 2 < set.len() && set[k + 1] == '-' {
+								ranges.push((set[k], set[k + 2]));
+								k += 3;
+							} else {
+								ranges.push((set[k], set[k]));
+								k += 1;
+							}
+						}
+						tokens.push(GlobToken::Set { negated, ranges });
+						i = end;
+					},
+					None => tokens.push(GlobToken::Literal('[')),
+				}
+			},
+			c => tokens.push(GlobToken::Literal(c)),
+		}
+		i += 1;
+	}
+	tokens
+}
+
+/// creates any missing parent directories of `path`, e.g. before writing a file into a directory that may not exist yet.
+/// Nothing is done if they already exist or `path` has no parent
+pub fn ensure_parent_dir(path:&Path) -> io::Result<()> {
+	match path.parent() {
+		Some(parent) if !parent.as_os_str().is_empty() => fs::create_dir_all(parent),
+		_ => Ok(()),
+	}
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -122,4 +386,196 @@ mod tests {
 		let result = format_bytes(1_000_000_000);
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_format_bytes_delta() {
+		assert_eq!(format_bytes_delta(2_411_725), "+2.3MB");
+		assert_eq!(format_bytes_delta(-512), "-512B");
+		assert_eq!(format_bytes_delta(-1_000_000), "-976.6KB");
+		assert_eq!(format_bytes_delta(0), "0B");
+		assert_eq!(format_bytes_delta(i64::MIN), "-8388608.0TB");
+    }
+
+    #[test]
+    fn test_write_atomic() {
+		let path = std::env::temp_dir().join("helper_lib_test_write_atomic.txt");
+		fs::write(&path, b"old contents that are longer than the new").unwrap();
+		write_atomic(&path, b"new contents").unwrap();
+		let contents = fs::read(&path).unwrap();
+		let temp_prefix = format!("helper_lib_test_write_atomic.txt.{}.", std::process::id());
+		let temp_exists = fs::read_dir(std::env::temp_dir()).unwrap()
+			.any(|entry| entry.unwrap().file_name().to_string_lossy().starts_with(&temp_prefix));
+		//clean up temp file
+		fs::remove_file(&path).unwrap();
+		assert_eq!(contents, b"new contents");
+		assert!(!temp_exists);
+    }
+
+    #[test]
+    fn test_write_atomic_concurrent() {
+		let path = std::env::temp_dir().join("helper_lib_test_write_atomic_concurrent.txt");
+		let writes: Vec<Vec<u8>> = (0..8u8).map(|i| vec![b'a' + i; 64 * 1024]).collect();
+		let results: Vec<io::Result<()>> = std::thread::scope(|scope| {
+			let handles: Vec<_> = writes.iter()
+				.map(|contents| scope.spawn(|| write_atomic(&path, contents)))
+				.collect();
+			handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+		});
+		let contents = fs::read(&path).unwrap();
+		//clean up temp file
+		fs::remove_file(&path).unwrap();
+		assert!(results.iter().all(|result| result.is_ok()));
+		assert!(writes.contains(&contents));
+    }
+
+    #[test]
+    fn test_write_atomic_missing_dir() {
+		let path = std::env::temp_dir().join("helper_lib_test_write_atomic_missing_dir").join("file.txt");
+		assert_eq!(write_atomic(&path, b"contents").unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_walk_files() {
+		let root = std::env::temp_dir().join("helper_lib_test_walk_files");
+		_ = fs::remove_dir_all(&root);
+		fs::create_dir_all(root.join("a").join("b")).unwrap();
+		fs::create_dir_all(root.join(".hidden_dir")).unwrap();
+		fs::write(root.join("top.txt"), b"").unwrap();
+		fs::write(root.join("a").join("middle.txt"), b"").unwrap();
+		fs::write(root.join("a").join("b").join("bottom.txt"), b"").unwrap();
+		fs::write(root.join("a").join(".hidden.txt"), b"").unwrap();
+		fs::write(root.join(".hidden_dir").join("inside.txt"), b"").unwrap();
+
+		let relative = |include_hidden: bool| {
+			let mut files: Vec<String> = walk_files(&root, false, include_hidden)
+				.map(|path| path_to_agnostic_relative(&path, &root))
+				.collect();
+			files.sort();
+			files
+		};
+		let visible = relative(false);
+		let all = relative(true);
+		//clean up temp dir
+		fs::remove_dir_all(&root).unwrap();
+
+		assert_eq!(visible, vec!["a/b/bottom.txt", "a/middle.txt", "top.txt"]);
+		assert_eq!(all, vec![".hidden_dir/inside.txt", "a/.hidden.txt", "a/b/bottom.txt", "a/middle.txt", "top.txt"]);
+    }
+
+	#[cfg(target_os = "linux")]
+    #[test]
+    fn test_walk_files_symlinks() {
+		let root = std::env::temp_dir().join("helper_lib_test_walk_files_symlinks");
+		_ = fs::remove_dir_all(&root);
+		fs::create_dir_all(root.join("a")).unwrap();
+		fs::write(root.join("a").join("file.txt"), b"").unwrap();
+		//a loop back to the root
+		std::os::unix::fs::symlink(&root, root.join("a").join("loop")).unwrap();
+
+		let not_followed = walk_files(&root, false, false).count();
+		let followed = walk_files(&root, true, false).count();
+		//clean up temp dir
+		fs::remove_dir_all(&root).unwrap();
+
+		assert_eq!(not_followed, 1);
+		assert_eq!(followed, 1);
+    }
+
+    #[test]
+    fn test_walk_files_missing_root() {
+		let root = std::env::temp_dir().join("helper_lib_test_walk_files_missing_root");
+		assert_eq!(walk_files(&root, false, false).count(), 0);
+    }
+
+    #[test]
+    fn test_is_hidden() {
+		assert!(is_hidden(Path::new("/home/ray/.bashrc")));
+		assert!(is_hidden(Path::new(".git")));
+		assert!(!is_hidden(Path::new("/home/ray/notes.txt")));
+		assert!(!is_hidden(Path::new("..")));
+    }
+
+    #[test]
+    fn test_is_subpath() {
+		let root = Path::new("/srv/files");
+		assert!(is_subpath(Path::new("/srv/files/docs/a.txt"), root));
+		assert!(is_subpath(Path::new("/srv/files"), root));
+		assert!(is_subpath(Path::new("/srv/files/./docs/../a.txt"), root));
+		assert!(!is_subpath(Path::new("/srv/other/a.txt"), root));
+		assert!(!is_subpath(Path::new("/srv/files_old/a.txt"), root));
+		assert!(!is_subpath(Path::new("/srv"), root));
+    }
+
+    #[test]
+    fn test_is_subpath_parent_dir_escape() {
+		let root = Path::new("/srv/files");
+		assert!(!is_subpath(Path::new("/srv/files/../../etc/passwd"), root));
+		assert!(!is_subpath(Path::new("/srv/files/docs/../../other"), root));
+		assert!(!is_subpath(Path::new("../secret"), Path::new("")));
+		assert!(is_subpath(Path::new("docs/a.txt"), Path::new("")));
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn test_is_subpath_windows_case() {
+		assert!(is_subpath(Path::new(r"C:\Users\Ray\Documents"), Path::new(r"c:\users\ray")));
+    }
+
+    #[test]
+    fn test_matches_glob_star() {
+		assert!(matches_glob("backup.tar.gz", "*.tar.gz"));
+		assert!(matches_glob(".tar.gz", "*.tar.gz"));
+		assert!(!matches_glob("backup.tar.gz.part", "*.tar.gz"));
+		assert!(!matches_glob("dir/backup.tar.gz", "*.tar.gz"));
+    }
+
+    #[test]
+    fn test_matches_glob_question_mark() {
+		assert!(matches_glob("log1.txt", "log?.txt"));
+		assert!(!matches_glob("log12.txt", "log?.txt"));
+		assert!(!matches_glob("log.txt", "log?.txt"));
+    }
+
+    #[test]
+    fn test_matches_glob_character_class() {
+		assert!(matches_glob("img_b.png", "img_[abc].png"));
+		assert!(!matches_glob("img_d.png", "img_[abc].png"));
+		assert!(matches_glob("img_7.png", "img_[0-9].png"));
+		assert!(matches_glob("img_d.png", "img_[!abc].png"));
+		assert!(!matches_glob("img_a.png", "img_[!abc].png"));
+		assert!(matches_glob("]", "[]]"));
+		assert!(matches_glob("[x", "[x"));
+    }
+
+    #[test]
+    fn test_matches_glob_literal() {
+		assert!(matches_glob("report (1).csv", "report (1).csv"));
+		assert!(!matches_glob("report 1.csv", "report (1).csv"));
+		assert!(matches_glob("a+b.txt", "a+b.txt"));
+    }
+
+    #[test]
+    fn test_matches_glob_multiple_stars() {
+		assert!(matches_glob("a-b-c.log", "*-*-*.log"));
+		assert!(!matches_glob("a-b.log", "*-*-*.log"));
+		assert!(!matches_glob("a/b-c.log", "*-*.log"));
+		assert!(matches_glob("img_-.png", "img_[a-].png"));
+    }
+
+    #[test]
+    fn test_ensure_parent_dir() {
+		let dir = std::env::temp_dir().join("helper_lib_test_ensure_parent_dir");
+		_ = fs::remove_dir_all(&dir);
+		let path = dir.join("a").join("b").join("file.txt");
+		ensure_parent_dir(&path).unwrap();
+		assert!(dir.join("a").join("b").is_dir());
+		assert!(!path.exists());
+		// already there
+		ensure_parent_dir(&path).unwrap();
+		// no parent
+		ensure_parent_dir(Path::new("file.txt")).unwrap();
+		ensure_parent_dir(Path::new("/")).unwrap();
+		fs::remove_dir_all(&dir).unwrap();
+    }
+
 }
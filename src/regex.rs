@@ -1,10 +1,13 @@
-﻿use regex::Regex;
+﻿use regex::{Captures, Regex, RegexBuilder};
+use std::{
+    collections::HashMap,
+    ops::Range,
+    str::FromStr,
+    sync::{Arc, Mutex, OnceLock},
+};
 
 pub fn match_to_string(string_to_search:&str, re:&Regex) -> Option<String> {
-    match re.find(string_to_search) {
-        Some(mat) => Some(mat.as_str().to_string()),
-        None => None
-    }
+    re.find(string_to_search).map(|mat| mat.as_str().to_string())
 }
 
 pub fn match_group_to_string(string_to_search:&str, re:&Regex, capturing_group:Option<usize>) -> Option<String> {
@@ -25,6 +28,178 @@ pub fn matches_group_to_vec(string_to_search:&str, re:&Regex, capturing_group:Op
         .collect()
 }
 
+/// replaces every match with `replacement`, which can reference capture groups as `$1` or `$name` (use `$$` for a literal `$`)
+pub fn replace_all(string_to_search:&str, re:&Regex, replacement:&str) -> String {
+    re.replace_all(string_to_search, replacement).into_owned()
+}
+
+/// replaces every match with the result of `f`, which is given the match's captures (`caps[0]` is the whole match)
+pub fn replace_with<F>(string_to_search:&str, re:&Regex, mut f:F) -> String
+where
+    F: FnMut(&Captures) -> String,
+{
+    re.replace_all(string_to_search, |caps: &Captures| f(caps)).into_owned()
+}
+
+/// as match_group_to_string, but for a named group `(?P<name>...)`. None if no match, or the group didn't participate
+pub fn named_group_to_string(string_to_search:&str, re:&Regex, name:&str) -> Option<String> {
+    re.captures(string_to_search)
+        .and_then(|caps| caps.name(name))
+        .map(|m| m.as_str().to_string())
+}
+
+/// as matches_group_to_vec, but for a named group `(?P<name>...)`. Matches where the group didn't participate are skipped
+pub fn named_groups_to_vec(string_to_search:&str, re:&Regex, name:&str) -> Vec<String> {
+    re.captures_iter(string_to_search)
+        .filter_map(|caps| Some(caps.name(name)?.as_str().to_string()))
+        .collect()
+}
+
+/// returns the byte range of every match, for slicing `string_to_search`
+pub fn match_ranges(string_to_search:&str, re:&Regex) -> Vec<Range<usize>> {
+    re.find_iter(string_to_search)
+        .map(|m| m.range())
+        .collect()
+}
+
+/// as match_ranges, but as char (unicode scalar value) indices, for highlighting text containing multibyte characters
+pub fn match_char_ranges(string_to_search:&str, re:&Regex) -> Vec<Range<usize>> {
+    // matches always start and end on a char boundary, so each byte offset maps to an exact char index.
+    // Count chars incrementally from the previous offset as matches are in order
+    let mut last_byte: usize = 0;
+    let mut last_char: usize = 0;
+    let mut char_index = |byte_index: usize| {
+        last_char += string_to_search[last_byte..byte_index].chars().count();
+        last_byte = byte_index;
+        last_char
+    };
+    re.find_iter(string_to_search)
+        .map(|m| {
+            let start = char_index(m.start());
+            start..char_index(m.end())
+        })
+        .collect()
+}
+
+/// splits on every match of `re`, returning the text between matches. Empty segments (from consecutive, leading or
+/// trailing delimiters) are dropped, so splitting only yields non-empty tokens
+pub fn split_by_regex(string_to_search:&str, re:&Regex) -> Vec<String> {
+    re.split(string_to_search)
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| segment.to_string())
+        .collect()
+}
+
+pub fn count_matches(string_to_search:&str, re:&Regex) -> usize {
+    re.find_iter(string_to_search).count()
+}
+
+pub fn is_match(string_to_search:&str, re:&Regex) -> bool {
+    re.is_match(string_to_search)
+}
+
+/// returns every capture group of every match, index 0 being the whole match. Groups that didn't participate are None
+pub fn all_groups(string_to_search:&str, re:&Regex) -> Vec<Vec<Option<String>>> {
+    re.captures_iter(string_to_search)
+        .map(|caps| {
+            caps.iter()
+                .map(|group| group.map(|m| m.as_str().to_string()))
+                .collect()
+        })
+        .collect()
+}
+
+/// compiles `pattern` once and returns the shared compiled instance on every later call with the same pattern.
+/// The cache is process-wide and never evicts, so it's intended for a bounded set of (usually literal) patterns
+pub fn cached_regex(pattern:&str) -> Result<Arc<Regex>, regex::Error> {
+    static CACHE: OnceLock<Mutex<HashMap<String, Arc<Regex>>>> = OnceLock::new();
+    let cache = CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    // a panic while holding the lock can't leave the map half-updated, so a poisoned lock is still usable
+    let mut cache = cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(re) = cache.get(pattern) {
+        return Ok(Arc::clone(re));
+    }
+    let re = Arc::new(Regex::new(pattern)?);
+    cache.insert(pattern.to_string(), Arc::clone(&re));
+    Ok(re)
+}
+
+/// as match_group_to_string, but parses the group into `T`. None if no match or the parse fails
+pub fn match_group_parse<T: FromStr>(string_to_search:&str, re:&Regex, capturing_group:usize) -> Option<T> {
+    re.captures(string_to_search)
+        .and_then(|caps| caps.get(capturing_group))
+        .and_then(|m| m.as_str().parse::<T>().ok())
+}
+
+/// compiles `pattern` case-insensitively
+pub fn compile_ci(pattern:&str) -> Result<Regex, regex::Error> {
+    compile_with_flags(pattern, true, false, false)
+}
+
+/// compiles `pattern` with the given flags, equivalent to the inline `(?i)`, `(?m)` and `(?s)` flags
+/// * `case_insensitive` - letters match both upper and lower case
+/// * `multi_line` - `^` and `$` match at the start and end of each line, not just the whole string
+/// * `dot_matches_new_line` - `.` also matches `\n`
+pub fn compile_with_flags(pattern:&str, case_insensitive:bool, multi_line:bool, dot_matches_new_line:bool) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(case_insensitive)
+        .multi_line(multi_line)
+        .dot_matches_new_line(dot_matches_new_line)
+        .build()
+}
+
+/// returns the text between each `open`/`close` pair, shortest match first (non-greedy), e.g. `{{name}}` -> `name`.
+/// The delimiters are literal text, not patterns. An `open` without a following `close` yields nothing
+pub fn extract_between(string_to_search:&str, open:&str, close:&str) -> Vec<String> {
+    let pattern = format!("(?s){}(.*?){}", regex::escape(open), regex::escape(close));
+    let re = Regex::new(&pattern).expect("escaped delimiters are always a valid pattern");
+    matches_group_to_vec(string_to_search, &re, Some(1))
+}
+
+/// replaces each `{{name}}` token in `template` with `values[name]`. Placeholders not in `values` are left untouched.
+/// Names are word characters (`[A-Za-z0-9_]`), optionally padded with spaces (`{{ name }}`). Write `\{{` for a literal `{{`
+pub fn substitute_placeholders(template:&str, values:&HashMap<String, String>) -> String {
+    let re = cached_regex(r"\\\{\{|\{\{\s*(\w+)\s*\}\}").expect("placeholder pattern is valid");
+    replace_with(template, &re, |caps| {
+        match caps.get(1) {
+            // escaped `\{{`
+            None => String::from("{{"),
+            Some(name) => match values.get(name.as_str()) {
+                Some(value) => value.clone(),
+                None => caps[0].to_string(),
+            },
+        }
+    })
+}
+
+/// as match_to_string, but the last match instead of the first, e.g. the last `[timestamp]` in a log line
+pub fn match_last_to_string(string_to_search:&str, re:&Regex) -> Option<String> {
+    re.find_iter(string_to_search)
+        .last()
+        .map(|m| m.as_str().to_string())
+}
+
+/// replaces each match with `mask` repeated once per char of the match, so the text keeps its length in chars,
+/// e.g. scrubbing emails from a log line before storing it
+pub fn redact_matches(string_to_search:&str, re:&Regex, mask:char) -> String {
+    replace_with(string_to_search, re, |caps| {
+        std::iter::repeat_n(mask, caps[0].chars().count()).collect()
+    })
+}
+
+/// wraps each match in `open` and `close`, e.g. `**` for markdown bold or ANSI colour codes. Matches are found left to right
+/// and never overlap, as in matches_to_vec. A pattern that can match empty text also wraps those empty matches
+pub fn highlight_matches(string_to_search:&str, re:&Regex, open:&str, close:&str) -> String {
+    replace_with(string_to_search, re, |caps| format!("{}{}{}", open, &caps[0], close))
+}
+
+/// true if the first match of `re` spans the whole of `string_to_search`, like anchoring the pattern with `^...$`
+/// without changing it. As matching is leftmost-first, put the longer alternative first (`ab|a`, not `a|ab`)
+pub fn is_full_match(string_to_search:&str, re:&Regex) -> bool {
+    re.find(string_to_search)
+        .is_some_and(|m| m.start() == 0 && m.end() == string_to_search.len())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -92,4 +267,296 @@ mod tests {
         assert_eq!(matches_group_to_vec(string_to_search, &re, Some(1)), expected);
     }
 
+	#[test]
+    fn test_replace_all() {
+        let re = Regex::new(r"image/").unwrap();
+        let string_to_search = "![x](image/x.png) ![y](image/y.png)";
+        let expected = String::from("![x](assets/x.png) ![y](assets/y.png)");
+        assert_eq!(replace_all(string_to_search, &re, "assets/"), expected);
+    }
+
+	#[test]
+    fn test_replace_all_capture_reference() {
+        let re = Regex::new(r"!\[(?P<name>[^\]]*)\]\(([^)]*)\)").unwrap();
+        let string_to_search = "blah ![name](image/path/x.png) blah";
+        let expected = String::from("blah <img alt=\"name\" src=\"image/path/x.png\"> blah");
+        assert_eq!(replace_all(string_to_search, &re, "<img alt=\"$name\" src=\"${2}\">"), expected);
+    }
+
+	#[test]
+    fn test_replace_all_none() {
+        let re = Regex::new(r"\d+").unwrap();
+        let string_to_search = "no digits here";
+        assert_eq!(replace_all(string_to_search, &re, "#"), string_to_search);
+    }
+
+	#[test]
+    fn test_replace_with_double_numbers() {
+        let re = Regex::new(r"\d+").unwrap();
+        let string_to_search = "width 20, height 150";
+        let expected = String::from("width 40, height 300");
+        let result = replace_with(string_to_search, &re, |caps| (caps[0].parse::<i64>().unwrap() * 2).to_string());
+        assert_eq!(result, expected);
+    }
+
+	#[test]
+    fn test_replace_with_unchanged() {
+        let re = Regex::new(r"\(([^)]*)\)").unwrap();
+        let string_to_search = "blah ![name](image/path/x.png) blah";
+        let result = replace_with(string_to_search, &re, |caps| caps[0].to_string());
+        assert_eq!(result, string_to_search);
+    }
+
+	#[test]
+    fn test_named_group_to_string() {
+        let re = Regex::new(r"\.(?P<ext>\w+)\)").unwrap();
+        let string_to_search = "![name](image/path/x.png)";
+        let expected = String::from("png");
+        assert_eq!(named_group_to_string(string_to_search, &re, "ext"), Some(expected));
+    }
+
+	#[test]
+    fn test_named_group_to_string_none() {
+        let re = Regex::new(r"\.(?P<ext>\w+)\)").unwrap();
+        let string_to_search = "![name](image/path/x)";
+        assert_eq!(named_group_to_string(string_to_search, &re, "ext"), None);
+        //unknown group name
+        assert_eq!(named_group_to_string("(x.png)", &re, "missing"), None);
+    }
+
+	#[test]
+    fn test_named_groups_to_vec() {
+        let re = Regex::new(r"\.(?P<ext>\w+)\)").unwrap();
+        let string_to_search = "blah ![name](image/path/x.png) blah blah ![name](image/path/y.jpg) blah";
+        let expected = vec![String::from("png"), String::from("jpg")];
+        assert_eq!(named_groups_to_vec(string_to_search, &re, "ext"), expected);
+    }
+
+	#[test]
+    fn test_match_ranges() {
+        let re = Regex::new(r"\d+").unwrap();
+        let string_to_search = "a1 bb22 ccc333";
+        let expected = vec![1..2, 5..7, 11..14];
+        assert_eq!(match_ranges(string_to_search, &re), expected);
+    }
+
+	#[test]
+    fn test_match_char_ranges_multibyte() {
+        let re = Regex::new(r"\d+").unwrap();
+        //'é' is 2 bytes and '日' is 3 bytes, so byte and char ranges differ after them
+        let string_to_search = "é12 日345";
+        assert_eq!(match_ranges(string_to_search, &re), vec![2..4, 8..11]);
+        assert_eq!(match_char_ranges(string_to_search, &re), vec![1..3, 5..8]);
+    }
+
+	#[test]
+    fn test_match_char_ranges_none() {
+        let re = Regex::new(r"\d+").unwrap();
+        let expected: Vec<Range<usize>> = vec![];
+        assert_eq!(match_char_ranges("no digits", &re), expected);
+    }
+
+	#[test]
+    fn test_split_by_regex() {
+        let re = Regex::new(r"[\s[:punct:]]+").unwrap();
+        let string_to_search = "one, two; three";
+        let expected = vec![String::from("one"), String::from("two"), String::from("three")];
+        assert_eq!(split_by_regex(string_to_search, &re), expected);
+    }
+
+	#[test]
+    fn test_split_by_regex_consecutive_delimiters() {
+        let re = Regex::new(r",").unwrap();
+        let string_to_search = "a,,b,,,c";
+        let expected = vec![String::from("a"), String::from("b"), String::from("c")];
+        assert_eq!(split_by_regex(string_to_search, &re), expected);
+    }
+
+	#[test]
+    fn test_split_by_regex_leading_trailing_delimiters() {
+        let re = Regex::new(r"\s+").unwrap();
+        let string_to_search = "  leading and trailing  ";
+        let expected = vec![String::from("leading"), String::from("and"), String::from("trailing")];
+        assert_eq!(split_by_regex(string_to_search, &re), expected);
+    }
+
+	#[test]
+    fn test_count_matches() {
+        let re = Regex::new(r"!\[.*?\]\(.*?\)").unwrap();
+        assert_eq!(count_matches("blah [name](image/path/x.png) blah", &re), 0);
+        assert_eq!(count_matches("blah ![name](image/path/x.png) blah", &re), 1);
+        assert_eq!(count_matches("![a](x.png) ![b](y.png) ![c](z.png)", &re), 3);
+    }
+
+	#[test]
+    fn test_is_match() {
+        let re = Regex::new(r"\d").unwrap();
+        assert!(!is_match("no digits", &re));
+        assert!(is_match("one 1", &re));
+        assert!(is_match("1 2 3", &re));
+    }
+
+	#[test]
+    fn test_all_groups_optional_group() {
+        let re = Regex::new(r"(\w+)(?:=(\w+))?").unwrap();
+        let string_to_search = "debug level=3";
+        let expected = vec![
+            vec![Some(String::from("debug")), Some(String::from("debug")), None],
+            vec![Some(String::from("level=3")), Some(String::from("level")), Some(String::from("3"))],
+        ];
+        assert_eq!(all_groups(string_to_search, &re), expected);
+    }
+
+	#[test]
+    fn test_all_groups_none() {
+        let re = Regex::new(r"(\w+)=(\w+)").unwrap();
+        let expected: Vec<Vec<Option<String>>> = vec![];
+        assert_eq!(all_groups("no pairs here", &re), expected);
+    }
+
+	#[test]
+    fn test_cached_regex_same_allocation() {
+        let first = cached_regex(r"\(([^)]*)\)").unwrap();
+        let second = cached_regex(r"\(([^)]*)\)").unwrap();
+        let other = cached_regex(r"\[([^\]]*)\]").unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(!Arc::ptr_eq(&first, &other));
+        assert_eq!(match_group_to_string("![name](x.png)", &second, Some(1)), Some(String::from("x.png")));
+    }
+
+	#[test]
+    fn test_cached_regex_invalid() {
+        assert!(cached_regex(r"(unclosed").is_err());
+    }
+
+	#[test]
+    fn test_match_group_parse() {
+        let re = Regex::new(r"port=(\S+)").unwrap();
+        assert_eq!(match_group_parse::<u16>("host=local port=8080", &re, 1), Some(8080));
+    }
+
+	#[test]
+    fn test_match_group_parse_fail() {
+        let re = Regex::new(r"port=(\S+)").unwrap();
+        assert_eq!(match_group_parse::<u16>("host=local port=http", &re, 1), None);
+        assert_eq!(match_group_parse::<u16>("host=local", &re, 1), None);
+    }
+
+	#[test]
+    fn test_compile_ci() {
+        let re = compile_ci(r"hello").unwrap();
+        assert_eq!(match_to_string("say HELLO there", &re), Some(String::from("HELLO")));
+    }
+
+	#[test]
+    fn test_compile_with_flags() {
+        let string_to_search = "first\nsecond";
+        let multi_line = compile_with_flags(r"^second$", false, true, false).unwrap();
+        let single_line = compile_with_flags(r"^second$", false, false, false).unwrap();
+        let dot_all = compile_with_flags(r"first.second", false, false, true).unwrap();
+        let dot = compile_with_flags(r"first.second", false, false, false).unwrap();
+        assert!(multi_line.is_match(string_to_search));
+        assert!(!single_line.is_match(string_to_search));
+        assert!(dot_all.is_match(string_to_search));
+        assert!(!dot.is_match(string_to_search));
+    }
+
+	#[test]
+    fn test_extract_between() {
+        let string_to_search = "Hello {{first}} {{last}}, see [link one] and [link two]";
+        assert_eq!(extract_between(string_to_search, "{{", "}}"), vec![String::from("first"), String::from("last")]);
+        assert_eq!(extract_between(string_to_search, "[", "]"), vec![String::from("link one"), String::from("link two")]);
+    }
+
+	#[test]
+    fn test_extract_between_unmatched() {
+        let expected: Vec<String> = vec![];
+        assert_eq!(extract_between("Hello {{first", "{{", "}}"), expected);
+        assert_eq!(extract_between("Hello first}}", "{{", "}}"), expected);
+    }
+
+	#[test]
+    fn test_substitute_placeholders() {
+        let mut values: HashMap<String, String> = HashMap::new();
+        values.insert(String::from("name"), String::from("Ray"));
+        values.insert(String::from("count"), String::from("3"));
+        let result = substitute_placeholders("Hi {{name}}, you have {{ count }} new messages", &values);
+        assert_eq!(result, "Hi Ray, you have 3 new messages");
+    }
+
+	#[test]
+    fn test_substitute_placeholders_unknown() {
+        let values: HashMap<String, String> = HashMap::new();
+        let result = substitute_placeholders("Hi {{name}}", &values);
+        assert_eq!(result, "Hi {{name}}");
+    }
+
+	#[test]
+    fn test_substitute_placeholders_escaped() {
+        let mut values: HashMap<String, String> = HashMap::new();
+        values.insert(String::from("name"), String::from("Ray"));
+        let result = substitute_placeholders(r"literal \{{name}} and {{name}}", &values);
+        assert_eq!(result, "literal {{name}} and Ray");
+    }
+
+	#[test]
+    fn test_match_last_to_string() {
+        let re = Regex::new(r"\[[^\]]*\]").unwrap();
+        let string_to_search = "[2025-01-01 10:00] start [2025-01-01 10:05] retry [2025-01-01 10:09] done";
+        assert_eq!(match_last_to_string(string_to_search, &re), Some(String::from("[2025-01-01 10:09]")));
+    }
+
+	#[test]
+    fn test_match_last_to_string_single_and_none() {
+        let re = Regex::new(r"\d+").unwrap();
+        assert_eq!(match_last_to_string("only 42 here", &re), Some(String::from("42")));
+        assert_eq!(match_last_to_string("no digits", &re), None);
+    }
+
+	#[test]
+    fn test_redact_matches() {
+        let re = Regex::new(r"\w+@\w+\.com").unwrap();
+        let string_to_search = "from ann@example.com to bob@test.com: ok";
+        let result = redact_matches(string_to_search, &re, '*');
+        assert_eq!(result, "from *************** to ************: ok");
+        assert_eq!(result.chars().count(), string_to_search.chars().count());
+    }
+
+	#[test]
+    fn test_redact_matches_multibyte() {
+        let re = Regex::new(r"é+").unwrap();
+        assert_eq!(redact_matches("caféé au lait", &re, '#'), "caf## au lait");
+        assert_eq!(redact_matches("no match", &re, '#'), "no match");
+    }
+
+	#[test]
+    fn test_highlight_matches() {
+        let re = Regex::new(r"\d+").unwrap();
+        assert_eq!(highlight_matches("rows 10 to 25 of 300", &re, "[", "]"), "rows [10] to [25] of [300]");
+        assert_eq!(highlight_matches("none", &re, "[", "]"), "none");
+    }
+
+	#[test]
+    fn test_highlight_matches_non_overlapping() {
+        let re = Regex::new(r"aa").unwrap();
+        assert_eq!(highlight_matches("aaaaa", &re, "**", "**"), "**aa****aa**a");
+    }
+
+	#[test]
+    fn test_is_full_match() {
+        let re = Regex::new(r"[A-Z]{3}-\d{4}").unwrap();
+        assert!(is_full_match("ABC-1234", &re));
+        assert!(!is_full_match("ABC-12345", &re));
+        assert!(!is_full_match("xABC-1234", &re));
+        assert!(!is_full_match("abc", &re));
+    }
+
+	#[test]
+    fn test_is_full_match_empty() {
+        let re = Regex::new(r"\d*").unwrap();
+        assert!(is_full_match("", &re));
+        assert!(is_full_match("123", &re));
+        assert!(!is_full_match("12a", &re));
+    }
+
 }
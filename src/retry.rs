@@ -0,0 +1,116 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::BuildHasher,
+    thread,
+    time::Duration,
+};
+
+/// calls `f` until it succeeds or has been tried `attempts` times (at least once), returning the last result.
+/// After the nth failure it sleeps for a random time between half and all of `base_delay * 2^(n-1)`, so the delay doubles
+/// each time and callers retrying together spread out.
+///
+/// # Example
+/// ```no_run
+/// use std::time::Duration;
+/// // e.g. a config file another process replaces now and then
+/// let config = helper_lib::retry::retry(5, Duration::from_millis(50), || std::fs::read_to_string("app.toml"));
+/// ```
+pub fn retry<T, E, F: FnMut() -> Result<T, E>>(attempts: usize, base_delay: Duration, f: F) -> Result<T, E> {
+    retry_if(attempts, base_delay, |_| true, f)
+}
+
+/// as retry, but gives up straight away on an error for which `should_retry` is false, e.g. to only retry `SQLITE_BUSY`
+pub fn retry_if<T, E, P, F>(attempts: usize, base_delay: Duration, mut should_retry: P, mut f: F) -> Result<T, E>
+where
+    P: FnMut(&E) -> bool,
+    F: FnMut() -> Result<T, E>,
+{
+    let mut attempt: usize = 1;
+    loop {
+        match f() {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt >= attempts || !should_retry(&e) => return Err(e),
+            Err(_) => {
+                thread::sleep(backoff_delay(base_delay, attempt));
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// the sleep after failed attempt number `attempt` (from 1): `base_delay * 2^(attempt-1)` with up to half taken off at random
+fn backoff_delay(base_delay: Duration, attempt: usize) -> Duration {
+    let exponent = (attempt - 1).min(31) as u32;
+    let delay = base_delay.saturating_mul(1 << exponent);
+    // std has no rng, but each RandomState is randomly seeded, which is plenty for jitter
+    let random = RandomState::new().hash_one(attempt);
+    let jitter_fraction = (random % 1_000) as f64 / 2_000.0;
+    delay.mul_f64(1.0 - jitter_fraction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_fails_twice_then_succeeds() {
+        let mut calls: usize = 0;
+        let result: Result<&str, String> = retry(5, Duration::from_millis(1), || {
+            calls += 1;
+            if calls < 3 {
+                Err(format!("failure {}", calls))
+            } else {
+                Ok("done")
+            }
+        });
+        assert_eq!(result, Ok("done"));
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn test_retry_gives_up() {
+        let mut calls: usize = 0;
+        let result: Result<(), usize> = retry(3, Duration::from_millis(1), || {
+            calls += 1;
+            Err(calls)
+        });
+        //the last error is returned
+        assert_eq!(result, Err(3));
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn test_retry_zero_attempts_tries_once() {
+        let mut calls: usize = 0;
+        let result: Result<(), ()> = retry(0, Duration::from_millis(1), || {
+            calls += 1;
+            Err(())
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_retry_if_stops_on_other_errors() {
+        let mut calls: usize = 0;
+        let result: Result<(), &str> = retry_if(5, Duration::from_millis(1), |e| *e == "busy", || {
+            calls += 1;
+            if calls == 1 { Err("busy") } else { Err("corrupt") }
+        });
+        assert_eq!(result, Err("corrupt"));
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        let base = Duration::from_millis(100);
+        for attempt in 1..=4 {
+            let full = base * (1 << (attempt - 1));
+            let delay = backoff_delay(base, attempt);
+            assert!(delay <= full && delay >= full / 2, "attempt {} delay {:?}", attempt, delay);
+        }
+        //the doubling stops at 2^31 rather than overflowing
+        let delay = backoff_delay(Duration::from_millis(1), 100);
+        assert!(delay >= Duration::from_millis(1 << 30) && delay <= Duration::from_millis(1 << 31));
+    }
+}
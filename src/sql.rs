@@ -1,12 +1,20 @@
-﻿use chrono::{DateTime, Local, Utc};
-use rusqlite::{Connection, Error, OptionalExtension, Row};
-use rusqlite::types::{ValueRef};
+﻿use chrono::{DateTime, Local, NaiveTime, Utc};
+use rusqlite::{params_from_iter, Connection, Error, OpenFlags, OptionalExtension, Row};
+use crate::{
+    strings::{count_graphemes, truncate_with_ellipsis},
+    HelperError,
+};
+use rusqlite::backup::Backup;
+use rusqlite::types::{FromSql, ToSql, Value, ValueRef};
 use std::{
     any::Any,
+    collections::HashMap,
     convert::TryFrom,
-    error::Error as StdError,
     fmt::Display,
+    hash::Hash,
+    io::Write,
     path::Path,
+    time::Duration,
 };
 
 pub enum CompOp {
@@ -90,15 +98,133 @@ where
         //return format!("{}datetime('{}')", comparison_prefix, s.format("%Y-%m-%d %H:%M:%S"));
     }
 
-    // --- All other Display types (i32, f64, structs, etc.) ---
+    // --- NaiveTime: a time of day stored as TEXT `HH:MM:SS`, written with time() as datetimes use datetime().
+    // Fractional seconds are dropped, as datetime() does ---
+    if let Some(t) = any_value.downcast_ref::<NaiveTime>() {
+        return format!("{}time('{}')", comparison_prefix, t.format("%H:%M:%S"));
+    }
+
+    // --- Uuid: stored as TEXT in lowercase hyphenated form ---
+    #[cfg(feature = "uuid")]
+    if let Some(u) = any_value.downcast_ref::<uuid::Uuid>() {
+        return format!("{}'{}'", comparison_prefix, u.hyphenated());
+    }
+
+    // --- Floats: NaN has no sql literal so becomes NULL, infinities use the same overflowing literal as sqlite's quote() ---
+    if let Some(f) = any_value.downcast_ref::<f64>() {
+        return format_float(*f, comparison_prefix);
+    }
+
+    if let Some(f) = any_value.downcast_ref::<f32>() {
+        return format_float(*f as f64, comparison_prefix);
+    }
+
+    // --- All other Display types (i32, structs, etc.) ---
     format!("{}{}", comparison_prefix, value)
 }
 
+/// as format_value_inner, but keeps milliseconds for `DateTime<Utc>`/`DateTime<Local>` using sqlite's 'subsec' modifier
+/// (without it `datetime()` truncates to whole seconds). All other types are formatted as in format_value_inner.
+fn format_value_precise<T>(value: &T, comparison_prefix: &str) -> String
+where
+    T: Display + Any + 'static,
+{
+    let any_value = value as &dyn Any;
+
+    if let Some(s) = any_value.downcast_ref::<DateTime<Utc>>() {
+        return format!("{}datetime('{}', 'subsec')", comparison_prefix, s.format("%Y-%m-%d %H:%M:%S%.3f"));
+    }
+
+    if let Some(s) = any_value.downcast_ref::<DateTime<Local>>() {
+        //convert local to utc, as in format_value_inner
+        return format!("{}datetime('{}', 'utc', 'subsec')", comparison_prefix, s.format("%Y-%m-%d %H:%M:%S%.3f"));
+    }
+
+    format_value_inner(value, comparison_prefix)
+}
+
+/// `NaN` -> `NULL`, `+inf` -> `9.0e+999`, `-inf` -> `-9.0e+999`, which sqlite reads back as infinite REALs.
+/// Display would otherwise emit `NaN`/`inf`, which sqlite treats as column names.
+fn format_float(f:f64, comparison_prefix:&str) -> String {
+    if f.is_nan() {
+        format!("{}NULL", comparison_prefix)
+    } else if f == f64::INFINITY {
+        format!("{}9.0e+999", comparison_prefix)
+    } else if f == f64::NEG_INFINITY {
+        format!("{}-9.0e+999", comparison_prefix)
+    } else {
+        format!("{}{}", comparison_prefix, f)
+    }
+}
+
+/// true if `value` is a NaN f64 or f32, which dbfmt_comp treats the same as None
+fn is_nan<T: Any>(value:&T) -> bool {
+    let any_value = value as &dyn Any;
+    if let Some(f) = any_value.downcast_ref::<f64>() {
+        return f.is_nan();
+    }
+    if let Some(f) = any_value.downcast_ref::<f32>() {
+        return f.is_nan();
+    }
+    false
+}
+
+/// wraps an identifier (table/column name) in double quotes, doubling any embedded quotes, so it can't break out of the statement
+fn quote_identifier(name:&str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// opens `dbfilepath`, or an in-memory database if `dbfilepath` is empty
+fn open_db(dbfilepath:&Path) -> Result<Connection, rusqlite::Error> {
+    if dbfilepath == Path::new("") {
+        Connection::open_in_memory()
+    } else {
+        Connection::open(dbfilepath)
+    }
+}
+
 // --- Public API Functions ---
 
+/// opens `dbfilepath` read only, so any attempt to modify the database returns an error. The file must already exist.
+/// The `_conn` helpers and the helpers taking a `&Connection` can be used safely against it.
+pub fn open_read_only(dbfilepath:&Path) -> Result<Connection, rusqlite::Error> {
+    Connection::open_with_flags(dbfilepath, OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_URI | OpenFlags::SQLITE_OPEN_NO_MUTEX)
+}
+
+/// opens a new in-memory database. The `_conn` helpers (e.g. `query_to_tuples_conn`) and the helpers taking a `&Connection`
+/// work against it, which makes it a convenient scratch database for unit tests. The database is dropped with the connection.
+pub fn open_in_memory() -> Result<Connection, rusqlite::Error> {
+    Connection::open_in_memory()
+}
+
+/// a pool of connections to one database file, shared safely between threads (clone it, or share a reference).
+/// `get` hands out a connection that derefs to `Connection`, so pass `&conn` to the `_conn` helpers and the others taking a
+/// `&Connection`; it goes back to the pool when dropped. Needs a file path: every in-memory connection would be a separate database.
+#[cfg(feature = "pool")]
+#[derive(Clone)]
+pub struct SqlitePool {
+    pool: r2d2::Pool<r2d2_sqlite::SqliteConnectionManager>,
+}
+
+#[cfg(feature = "pool")]
+impl SqlitePool {
+    /// opens `size` connections to `dbfilepath`, creating the file if needed. Errors if they can't be opened in time
+    pub fn new(dbfilepath:&Path, size:u32) -> Result<Self, HelperError> {
+        let manager = r2d2_sqlite::SqliteConnectionManager::file(dbfilepath);
+        let pool = r2d2::Pool::builder().max_size(size).build(manager)?;
+        Ok(SqlitePool { pool })
+    }
+
+    /// a connection from the pool, waiting (up to r2d2's 30 second default) if they are all in use
+    pub fn get(&self) -> Result<r2d2::PooledConnection<r2d2_sqlite::SqliteConnectionManager>, HelperError> {
+        Ok(self.pool.get()?)
+    }
+}
+
 /// Formats a bare value (T). Since the value is not an Option, it cannot be None.
 ///
 /// This is used when you know the value is present (e.g., `let x = 42;`).
+/// A NaN float is written as `NULL` and infinities as `9.0e+999`/`-9.0e+999`.
 ///
 /// # Arguments
 /// * `input` - A reference to the bare value.
@@ -120,16 +246,57 @@ where
     T: Display + Any + 'static,
 {
     match input {
-        None => format!("NULL"),
+        None => String::from("NULL"),
         Some(value) => format_value_inner(&value, ""),
     }
 }
 
-/// as dbfmt, but prefixes a comparison operator. '=' for Some(), 'IS' for None()
+/// as dbfmt_t, but datetimes keep millisecond precision, e.g. `datetime('2023-12-25 14:30:45.123', 'subsec')`.
+/// sqlite stores this as TEXT `YYYY-MM-DD HH:MM:SS.SSS`, so anything reading the column must accept the fractional seconds
+/// (the chrono `FromSql` used by `query_single_row_to_tuple` does). Compare such columns against precise values only,
+/// as `'... 14:30:45.123' > '... 14:30:45'` as text.
+pub fn dbfmt_t_precise<T>(input: &T) -> String
+where
+    T: Display + Any + 'static,
+{
+    format_value_precise(input, "")
+}
+
+/// as dbfmt, but datetimes keep millisecond precision. See dbfmt_t_precise
+pub fn dbfmt_precise<T>(input: Option<T>) -> String
+where
+    T: Display + Any + 'static,
+{
+    match input {
+        None => String::from("NULL"),
+        Some(value) => format_value_precise(&value, ""),
+    }
+}
+
+/// formats an enum stored as its integer discriminant, rather than its Display text. Give the enum a `From` impl for i64
+/// (which provides `Into<i64>`):
+/// ```
+/// #[derive(Clone, Copy)]
+/// #[repr(i64)]
+/// enum Status { Active = 1, Archived = 2 }
+///
+/// impl From<Status> for i64 {
+///     fn from(status: Status) -> i64 { status as i64 }
+/// }
+///
+/// assert_eq!(helper_lib::sql::dbfmt_enum_repr(Status::Archived), "2");
+/// ```
+pub fn dbfmt_enum_repr<E: Into<i64> + Copy>(e: E) -> String {
+    let repr: i64 = e.into();
+    repr.to_string()
+}
+
+/// as dbfmt, but prefixes a comparison operator. '=' for Some(), 'IS' for None(). A NaN float is treated as None
 pub fn dbfmt_comp<T>(input: Option<T>, comparison_operator: CompOp) -> String
 where
     T: Display + Any + 'static,
 {
+    let input = input.filter(|value| !is_nan(value));
     match input {
         None => {
 			let co = match comparison_operator {
@@ -152,60 +319,106 @@ where
     }
 }
 
+/// list filter: `" IN (1, 2, 3)"`, or `" NOT IN (...)"` when `negate`. Values are formatted as in dbfmt.
+/// None or an empty list gives `" IN ()"` (never matches) or, negated, `" NOT IN ()"` (always matches). sqlite accepts empty lists
+/// `format!("c{}", dbfmt_comp_in(Some(&[1, 2][..]), false))` -> `c IN (1, 2)`
+pub fn dbfmt_comp_in<T>(values: Option<&[T]>, negate: bool) -> String
+where
+    T: Display + Any + 'static,
+{
+    let co = if negate { " NOT IN " } else { " IN " };
+    let list = values
+        .unwrap_or_default()
+        .iter()
+        .map(|value| format_value_inner(value, ""))
+        .collect::<Vec<String>>()
+        .join(", ");
+    format!("{}({})", co, list)
+}
+
+/// converts a single sqlite value to i64. INTEGER as is, REAL truncated, TEXT parsed. Error on NULL, BLOB or failed parse
+fn value_ref_to_i64(value_ref:ValueRef) -> Result<i64, rusqlite::Error> {
+    let converted_value: i64 = match value_ref {
+        // 1. INTEGER: Direct conversion
+        ValueRef::Integer(i) => i,
+        
+        // 2. REAL: Convert to i64 by truncation (standard Rust f64 as i64)
+        ValueRef::Real(f) => f as i64, 
+        
+        // 3. TEXT: Attempt to parse the string into an i64
+        ValueRef::Text(bytes) => {
+            // Convert the byte slice to a UTF-8 string, then parse
+            let s = std::str::from_utf8(bytes)
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e)))?;
+            
+            s.parse::<i64>()
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e)))?
+        }
+        
+        // 4. NULL: Handle as an error (query_to_i64_or returns a default instead)
+        ValueRef::Null => {
+            return Err(rusqlite::Error::InvalidColumnType(0, String::from("NULL not an integer"), rusqlite::types::Type::Null));
+        }
+        
+        // 5. BLOB: Cannot convert arbitrary binary data to i64
+        ValueRef::Blob(_) => {
+            return Err(rusqlite::Error::InvalidColumnType(0, String::from("BLOB not an integer"), rusqlite::types::Type::Blob));
+        }
+    };
+
+    Ok(converted_value)
+}
+
 /// returns the first column of the first row to i64, or none if no rows. Error on NULL or failed cast
-pub fn query_to_i64(dbfilepath:&Path, sql:&str) -> Result<Option<i64>, Box<dyn StdError>> {
-    let conn: Connection;
-    if dbfilepath == Path::new("") {
-        conn = Connection::open_in_memory()?;
-    } else {
-        conn = Connection::open(&dbfilepath)?;
-    }
+pub fn query_to_i64(dbfilepath:&Path, sql:&str) -> Result<Option<i64>, HelperError> {
+    let conn = open_db(dbfilepath)?;
     
     let result: Option<i64> = conn.query_row(sql, [], |row| {
-        let value_ref = row.get_ref(0)?;
+        value_ref_to_i64(row.get_ref(0)?)
+    }).optional()?;
 
-        let converted_value: i64 = match value_ref {
-            // 1. INTEGER: Direct conversion
-            ValueRef::Integer(i) => i,
-            
-            // 2. REAL: Convert to i64 by truncation (standard Rust f64 as i64)
-            ValueRef::Real(f) => f as i64, 
-            
-            // 3. TEXT: Attempt to parse the string into an i64
-            ValueRef::Text(bytes) => {
-                // Convert the byte slice to a UTF-8 string, then parse
-                let s = std::str::from_utf8(bytes)
-                    .map_err(|e| rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e)))?;
-                
-                s.parse::<i64>()
-                    .map_err(|e| rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e)))?
-            }
-            
-            // 4. NULL: Handle as an error within the row closure (or you could return a default)
-            ValueRef::Null => {
-                return Err(rusqlite::Error::InvalidColumnType(0, String::from("NULL not an integer"), rusqlite::types::Type::Null));
-            }
-            
-            // 5. BLOB: Cannot convert arbitrary binary data to i64
-            ValueRef::Blob(_) => {
-                return Err(rusqlite::Error::InvalidColumnType(0, String::from("BLOB not an integer"), rusqlite::types::Type::Blob));
-            }
-        };
+    Ok(result)
+}
 
-        Ok(converted_value)
+/// as query_to_i64, but returns `default` when there are no rows or the value is NULL (e.g. `SELECT SUM(x)` on an empty table).
+/// Still errors on a failed cast.
+pub fn query_to_i64_or(dbfilepath:&Path, sql:&str, default:i64) -> Result<i64, HelperError> {
+    let conn = open_db(dbfilepath)?;
+    
+    let result: Option<Option<i64>> = conn.query_row(sql, [], |row| {
+        match row.get_ref(0)? {
+            ValueRef::Null => Ok(None),
+            value_ref => value_ref_to_i64(value_ref).map(Some),
+        }
     }).optional()?;
 
-    return Ok(result);
+    Ok(result.flatten().unwrap_or(default))
 }
 
-/// returns the first column of the first row to String, or None if NULL. Error on no rows or failed cast
-pub fn query_to_string(dbfilepath:&Path, sql:&str) -> Result<Option<String>, Box<dyn StdError>> {
-    let conn: Connection;
-    if dbfilepath == Path::new("") {
-        conn = Connection::open_in_memory()?;
-    } else {
-        conn = Connection::open(&dbfilepath)?;
+/// converts a single sqlite value to String, or None if NULL. BLOBs become lowercase hex
+fn value_ref_to_string(value_ref:ValueRef) -> Option<String> {
+    match value_ref {
+        ValueRef::Null => None,
+        // For INTEGER and REAL, use format! to convert to String without relying 
+        // on the strict FromSql<String> implementation.
+        ValueRef::Integer(i) => Some(format!("{}", i)),
+        ValueRef::Real(f) => Some(format!("{}", f)),
+        // BLOB: Convert byte slice to a hexadecimal String.
+        ValueRef::Blob(bytes) => {
+            // Encode the bytes as a lowercase hex string
+            Some(crate::hex::to_hex(bytes))
+        },
+        // If it's Text, safely convert the byte slice to a String.
+        ValueRef::Text(bytes) => {
+            let formatted_string: String = String::from_utf8_lossy(bytes).to_string();
+            Some(formatted_string)
+        }
     }
+}
+
+/// returns the first column of the first row to String, or None if NULL. Error on no rows or failed cast
+pub fn query_to_string(dbfilepath:&Path, sql:&str) -> Result<Option<String>, HelperError> {
+    let conn = open_db(dbfilepath)?;
     
     // 2. Execute the query using query_row
     let result = conn.query_row(
@@ -213,25 +426,8 @@ pub fn query_to_string(dbfilepath:&Path, sql:&str) -> Result<Option<String>, Box
         [], // No parameters for this example, use `params!` or `&[]` for bind parameters
         |row| {
             // This closure maps a single row to the desired output.
-            // We use get_raw(0) to check for NULL before attempting to convert to String.
-            match row.get_ref(0)? {
-                ValueRef::Null => Ok(None),
-                // For INTEGER and REAL, use format! to convert to String without relying 
-                // on the strict FromSql<String> implementation.
-                ValueRef::Integer(i) => Ok(Some(format!("{}", i))),
-                ValueRef::Real(f) => Ok(Some(format!("{}", f))),
-                // BLOB: Convert byte slice to a hexadecimal String.
-                ValueRef::Blob(bytes) => {
-                    // Use the hex crate to encode the bytes into a lowercase hex string
-                    Ok(Some(hex::encode(bytes)))
-                },
-                // If it's Text, safely convert the byte slice to a String.
-                ValueRef::Text(bytes) => {
-                    // let formatted_string: String = row.get(0)?;
-                    let formatted_string: String = String::from_utf8_lossy(bytes).to_string();
-                    Ok(Some(formatted_string))
-                }
-            }
+            // We use get_ref(0) to check for NULL before attempting to convert to String.
+            Ok(value_ref_to_string(row.get_ref(0)?))
         },
     )?;
 
@@ -239,6 +435,20 @@ pub fn query_to_string(dbfilepath:&Path, sql:&str) -> Result<Option<String>, Box
     Ok(result)
 }
 
+/// returns true if `sql` returns at least one row, without reading the rows. Runs `SELECT EXISTS(<sql>)`
+pub fn query_exists(dbfilepath:&Path, sql:&str) -> Result<bool, HelperError> {
+    let conn = open_db(dbfilepath)?;
+    query_exists_conn(&conn, sql)
+}
+
+/// as query_exists, but against an already open connection
+pub fn query_exists_conn(conn:&Connection, sql:&str) -> Result<bool, HelperError> {
+    // a trailing ';' is fine as a statement but not inside the EXISTS(...) subquery
+    let inner_sql = sql.trim().trim_end_matches(';');
+    let exists: bool = conn.query_row(&format!("SELECT EXISTS({})", inner_sql), [], |row| row.get(0))?;
+    Ok(exists)
+}
+
 pub fn query_single_row_to_tuple<T>(dbfilepath:&Path, sql:&str) -> Result<Option<T>, rusqlite::Error> 
 where
     // The trait bound remains correct!
@@ -247,12 +457,7 @@ where
         Error = Error 
     >
 {
-    let conn: Connection;
-    if dbfilepath == Path::new("") {
-        conn = Connection::open_in_memory()?;
-    } else {
-        conn = Connection::open(&dbfilepath)?;
-    }
+    let conn = open_db(dbfilepath)?;
     
     // 1. Use query_map instead of query_row
     let mut stmt = conn.prepare(sql)?;
@@ -296,12 +501,7 @@ where
         Error = Error 
     >
 {
-    let conn: Connection;
-    if dbfilepath == Path::new("") {
-        conn = Connection::open_in_memory()?;
-    } else {
-        conn = Connection::open(&dbfilepath)?;
-    }
+    let conn = open_db(dbfilepath)?;
     
     // 1. Prepare the SQL statement.
     let mut stmt = conn.prepare(sql)?;
@@ -323,7 +523,26 @@ where
     result_vec
 }
 
-pub fn query_to_tuples_conn<T>(conn:Connection, sql:&str) -> Result<Vec<T>, rusqlite::Error> 
+/// as query_to_tuples, but runs the blocking query on tokio's blocking thread pool so it doesn't stall the async runtime.
+/// Must be awaited inside a tokio runtime. A panic in the query is resumed in the caller
+#[cfg(feature = "async")]
+pub async fn query_to_tuples_async<T>(dbfilepath:&Path, sql:&str) -> Result<Vec<T>, rusqlite::Error> 
+where
+    for<'r> T: TryFrom<
+        &'r Row<'r>, 
+        Error = Error 
+    >,
+    T: Send + 'static,
+{
+    let dbfilepath = dbfilepath.to_path_buf();
+    let sql = sql.to_string();
+    tokio::task::spawn_blocking(move || query_to_tuples::<T>(&dbfilepath, &sql))
+        .await
+        .unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()))
+}
+
+/// as query_to_tuples, but against an already open connection (e.g. from `open_in_memory`)
+pub fn query_to_tuples_conn<T>(conn:&Connection, sql:&str) -> Result<Vec<T>, rusqlite::Error> 
 where
     // T must implement TryFrom<&Row> for *any* lifetime 'r (HRTB remains crucial)
     for<'r> T: TryFrom<
@@ -351,15 +570,28 @@ where
     result_vec
 }
 
+/// as query_to_tuples_conn, but calls `f` with each converted row as it is read instead of collecting them,
+/// so large result sets are streamed without buffering. Stops at the first conversion error.
+pub fn for_each_row<T, F>(conn:&Connection, sql:&str, mut f:F) -> Result<(), rusqlite::Error>
+where
+    for<'r> T: TryFrom<
+        &'r Row<'r>, 
+        Error = Error 
+    >,
+    F: FnMut(T),
+{
+    let mut stmt = conn.prepare(sql)?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        f(T::try_from(row)?);
+    }
+    Ok(())
+}
+
 ///execute sql to dbfilepath, void return. Can execute multiple statements within `sql` separated by ";"
 pub fn execute_batch(dbfilepath:&Path, sql:&str) -> Result<(), rusqlite::Error> 
 {
-    let conn: Connection;
-    if dbfilepath == Path::new("") {
-        conn = Connection::open_in_memory()?;
-    } else {
-        conn = Connection::open(&dbfilepath)?;
-    }
+    let conn = open_db(dbfilepath)?;
     
     conn.execute_batch(sql)
 }
@@ -367,12 +599,7 @@ pub fn execute_batch(dbfilepath:&Path, sql:&str) -> Result<(), rusqlite::Error>
 ///execute sql to dbfilepath, return number of rows changed. Single statement only.
 pub fn execute_return_changed_rows(dbfilepath:&Path, sql:&str) -> Result<usize, rusqlite::Error> 
 {
-    let conn: Connection;
-    if dbfilepath == Path::new("") {
-        conn = Connection::open_in_memory()?;
-    } else {
-        conn = Connection::open(&dbfilepath)?;
-    }
+    let conn = open_db(dbfilepath)?;
     
     conn.execute(sql, [])
 }
@@ -380,18 +607,409 @@ pub fn execute_return_changed_rows(dbfilepath:&Path, sql:&str) -> Result<usize,
 ///execute sql to dbfilepath, return last rowid. Can execute multiple statements within `sql` separated by ";"
 pub fn execute_return_last_rowid(dbfilepath:&Path, sql:&str) -> Result<i64, rusqlite::Error> 
 {
-    let conn: Connection;
-    if dbfilepath == Path::new("") {
-        conn = Connection::open_in_memory()?;
-    } else {
-        conn = Connection::open(&dbfilepath)?;
-    }
+    let conn = open_db(dbfilepath)?;
     
     conn.execute_batch(sql)?;
     
     Ok(conn.last_insert_rowid())
 }
 
+/// returns true if a table (or view) called `name` exists in the database
+pub fn table_exists(conn:&Connection, name:&str) -> Result<bool, rusqlite::Error> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type IN ('table', 'view') AND name = ?1)",
+        [name],
+        |row| row.get(0),
+    )
+}
+
+/// returns true if `table` has a column called `column`. Uses the table-valued form of `PRAGMA table_info` so both names can be bound.
+pub fn column_exists(conn:&Connection, table:&str, column:&str) -> Result<bool, rusqlite::Error> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2)",
+        [table, column],
+        |row| row.get(0),
+    )
+}
+
+/// runs each `(version_id, sql)` migration that has not already been recorded in the `_migrations` table, in order.
+/// Each migration runs in its own transaction together with its `_migrations` record, so a failing migration is rolled back
+/// and stops the run, leaving earlier migrations applied. Re-running with the same migrations is a no-op.
+pub fn run_migrations(conn:&Connection, migrations:&[(&str, &str)]) -> Result<(), HelperError> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS _migrations (
+            version_id TEXT PRIMARY KEY NOT NULL,
+            applied_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );"
+    )?;
+
+    for (version_id, sql) in migrations {
+        let already_run: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM _migrations WHERE version_id = ?1)",
+            [version_id],
+            |row| row.get(0),
+        )?;
+        if already_run {
+            continue;
+        }
+
+        // unchecked_transaction as we only hold a shared reference; it rolls back on drop unless committed
+        let tx = conn.unchecked_transaction()?;
+        tx.execute_batch(sql)?;
+        tx.execute("INSERT INTO _migrations (version_id) VALUES (?1)", [version_id])?;
+        tx.commit()?;
+    }
+
+    Ok(())
+}
+
+/// sets `PRAGMA journal_mode=WAL` so readers don't block the writer. The mode persists in the database file.
+/// In-memory databases ignore this and stay in `memory` journal mode.
+pub fn enable_wal_mode(conn:&Connection) -> Result<(), rusqlite::Error> {
+    // journal_mode returns the resulting mode as a row, so it must be read back rather than executed
+    let _mode: String = conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get(0))?;
+    Ok(())
+}
+
+/// sets how long, in milliseconds, a statement waits on a locked database before returning `SQLITE_BUSY`
+pub fn set_busy_timeout(conn:&Connection, ms:u64) -> Result<(), rusqlite::Error> {
+    conn.busy_timeout(Duration::from_millis(ms))
+}
+
+/// turns `PRAGMA foreign_keys` enforcement on or off for this connection (sqlite defaults to off)
+pub fn set_foreign_keys(conn:&Connection, enabled:bool) -> Result<(), rusqlite::Error> {
+    conn.pragma_update(None, "foreign_keys", enabled)
+}
+
+/// executes `insert_sql` once per row inside a single transaction, returning the total rows inserted.
+/// Each row holds sql literals as produced by `dbfmt`/`dbfmt_t` (`'text'`, numbers, `NULL`, `datetime('...')`), bound in
+/// order to the statement's `?` placeholders. The statement is prepared once; quoted strings, numbers and NULL are
+/// unquoted in rust, and any other expression is evaluated by sqlite before binding. Any failing row rolls back the whole batch.
+pub fn batch_insert(conn:&Connection, insert_sql:&str, rows:&[Vec<String>]) -> Result<usize, HelperError> {
+    let tx = conn.unchecked_transaction()?;
+    let mut inserted: usize = 0;
+    {
+        let mut stmt = tx.prepare(insert_sql)?;
+        for row in rows {
+            let values = row.iter()
+                .map(|literal| sql_literal_to_value(&tx, literal))
+                .collect::<Result<Vec<Value>, Error>>()?;
+            inserted += stmt.execute(params_from_iter(values.iter()))?;
+        }
+    }
+    tx.commit()?;
+    Ok(inserted)
+}
+
+/// converts one `dbfmt` literal back into the value it stands for, so it can be bound to a placeholder
+fn sql_literal_to_value(conn:&Connection, literal:&str) -> Result<Value, Error> {
+    let trimmed = literal.trim();
+    if trimmed.eq_ignore_ascii_case("NULL") {
+        return Ok(Value::Null);
+    }
+    if let Some(inner) = trimmed.strip_prefix('\'').and_then(|s| s.strip_suffix('\''))
+        && !inner.replace("''", "").contains('\'')
+    {
+        return Ok(Value::Text(inner.replace("''", "'")));
+    }
+    if let Ok(i) = trimmed.parse::<i64>() {
+        return Ok(Value::Integer(i));
+    }
+    //only numeric characters, so rust's "inf"/"NaN" spellings are left to sqlite like any other word
+    if trimmed.chars().all(|c| c.is_ascii_digit() || "+-.eE".contains(c))
+        && let Ok(f) = trimmed.parse::<f64>()
+    {
+        return Ok(Value::Real(f));
+    }
+    //datetime('...'), time('...') and any other expression
+    conn.prepare_cached(&format!("SELECT {}", trimmed))?
+        .query_row([], |row| row.get(0))
+}
+
+/// inserts one row, or updates the existing row when it conflicts on `conflict_cols`. Returns rows changed.
+/// `values` are sql literals in the same order as `columns`, e.g. from `dbfmt`/`dbfmt_t`. Table and column names are quoted.
+/// On conflict every column not in `conflict_cols` is set to its new value; if there are none the insert is ignored.
+///
+/// # Example
+/// `upsert(&conn, "settings", &["key", "value"], &[dbfmt_t(&"theme"), dbfmt_t(&"dark")], &["key"])`
+/// -> `INSERT INTO "settings" ("key", "value") VALUES ('theme', 'dark') ON CONFLICT ("key") DO UPDATE SET "value" = excluded."value"`
+pub fn upsert(conn:&Connection, table:&str, columns:&[&str], values:&[String], conflict_cols:&[&str]) -> Result<usize, HelperError> {
+    if columns.is_empty() || columns.len() != values.len() {
+        return Err(HelperError::InvalidInput(format!("upsert: {} columns but {} values", columns.len(), values.len())));
+    }
+    if conflict_cols.is_empty() {
+        return Err(HelperError::InvalidInput(String::from("upsert: at least one conflict column is required")));
+    }
+
+    let column_list = columns.iter().map(|c| quote_identifier(c)).collect::<Vec<String>>().join(", ");
+    let conflict_list = conflict_cols.iter().map(|c| quote_identifier(c)).collect::<Vec<String>>().join(", ");
+    let update_list = columns.iter()
+        .filter(|c| !conflict_cols.contains(c))
+        .map(|c| format!("{0} = excluded.{0}", quote_identifier(c)))
+        .collect::<Vec<String>>()
+        .join(", ");
+    let conflict_action = if update_list.is_empty() {
+        String::from("DO NOTHING")
+    } else {
+        format!("DO UPDATE SET {}", update_list)
+    };
+
+    let sql = format!(
+        "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT ({}) {}",
+        quote_identifier(table), column_list, values.join(", "), conflict_list, conflict_action
+    );
+    Ok(conn.execute(&sql, [])?)
+}
+
+/// quotes a csv field per RFC 4180 if it contains a comma, double quote, CR or LF. Embedded quotes are doubled
+fn csv_field(field:&str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// writes the result of `sql` to `writer` as RFC 4180 csv: a header row of column names, then one line per row, CRLF line endings.
+/// Values are formatted as in query_to_string, with NULL as an empty field. Returns the number of data rows written.
+pub fn query_to_csv<W: Write>(conn:&Connection, sql:&str, mut writer:W) -> Result<usize, HelperError> {
+    let mut stmt = conn.prepare(sql)?;
+    let column_count = stmt.column_count();
+
+    let header = stmt.column_names().iter().map(|name| csv_field(name)).collect::<Vec<String>>().join(",");
+    write!(writer, "{}\r\n", header)?;
+
+    let mut rows = stmt.query([])?;
+    let mut row_count: usize = 0;
+    while let Some(row) = rows.next()? {
+        let mut fields: Vec<String> = Vec::with_capacity(column_count);
+        for icol in 0..column_count {
+            let value = value_ref_to_string(row.get_ref(icol)?).unwrap_or_default();
+            fields.push(csv_field(&value));
+        }
+        write!(writer, "{}\r\n", fields.join(","))?;
+        row_count += 1;
+    }
+    writer.flush()?;
+
+    Ok(row_count)
+}
+
+/// converts a single sqlite value to json. INTEGER/REAL -> number, TEXT -> string, BLOB -> base64 string, NULL -> null.
+/// Non-finite REALs have no json number so become null
+#[cfg(feature = "json")]
+fn value_ref_to_json(value_ref:ValueRef) -> serde_json::Value {
+    match value_ref {
+        ValueRef::Null => serde_json::Value::Null,
+        ValueRef::Integer(i) => serde_json::Value::from(i),
+        ValueRef::Real(f) => serde_json::Number::from_f64(f).map_or(serde_json::Value::Null, serde_json::Value::Number),
+        ValueRef::Text(bytes) => serde_json::Value::String(String::from_utf8_lossy(bytes).to_string()),
+        ValueRef::Blob(bytes) => serde_json::Value::String(crate::base64::encode(bytes)),
+    }
+}
+
+/// returns the result of `sql` as a json array with one object per row, keyed by column name
+#[cfg(feature = "json")]
+pub fn query_to_json(conn:&Connection, sql:&str) -> Result<serde_json::Value, HelperError> {
+    let rows = query_to_value_maps(conn, sql)?;
+    Ok(serde_json::Value::Array(rows.into_iter().map(serde_json::Value::Object).collect()))
+}
+
+/// returns each row of `sql` as a map of column name to json value (converted as in query_to_json), in column order.
+/// If two columns share a name the later one wins
+#[cfg(feature = "json")]
+pub fn query_to_value_maps(conn:&Connection, sql:&str) -> Result<Vec<serde_json::Map<String, serde_json::Value>>, HelperError> {
+    let mut stmt = conn.prepare(sql)?;
+    let column_names: Vec<String> = stmt.column_names().iter().map(|name| name.to_string()).collect();
+
+    let mut rows = stmt.query([])?;
+    let mut maps: Vec<serde_json::Map<String, serde_json::Value>> = Vec::new();
+    while let Some(row) = rows.next()? {
+        let mut map = serde_json::Map::new();
+        for (icol, column_name) in column_names.iter().enumerate() {
+            map.insert(column_name.clone(), value_ref_to_json(row.get_ref(icol)?));
+        }
+        maps.push(map);
+    }
+
+    Ok(maps)
+}
+
+// --- Parameter binding variants ---
+// Values are bound to `?1`, `?2`, ... placeholders by sqlite instead of being formatted into the sql,
+// so untrusted input is always treated as data. e.g. `query_to_tuples_params(&conn, "SELECT c FROM t WHERE name = ?1", &[&name])`
+
+/// as query_to_tuples_conn, but binds `params` to the placeholders in `sql`
+pub fn query_to_tuples_params<T>(conn:&Connection, sql:&str, params:&[&dyn ToSql]) -> Result<Vec<T>, rusqlite::Error> 
+where
+    for<'r> T: TryFrom<
+        &'r Row<'r>, 
+        Error = Error 
+    >
+{
+    let mut stmt = conn.prepare(sql)?;
+    let rows_result = stmt.query_map(params, |row| T::try_from(row))?;
+    rows_result.collect()
+}
+
+/// as query_to_tuples_params, but returns one page of `sql`: `LIMIT ?1 OFFSET ?2` is appended and `limit`, `offset` bound to it.
+/// `sql` must not have its own placeholders or LIMIT; a trailing `;` is dropped. Give it an ORDER BY so the pages are stable
+pub fn query_to_tuples_paged<T>(conn:&Connection, sql:&str, limit:i64, offset:i64) -> Result<Vec<T>, rusqlite::Error> 
+where
+    for<'r> T: TryFrom<
+        &'r Row<'r>, 
+        Error = Error 
+    >
+{
+    let base_sql = sql.trim_end().trim_end_matches(';');
+    let paged_sql = format!("{} LIMIT ?1 OFFSET ?2", base_sql);
+    query_to_tuples_params(conn, &paged_sql, &[&limit, &offset])
+}
+
+/// as query_single_row_to_tuple, but binds `params` to the placeholders in `sql`. None if no rows
+pub fn query_single_row_to_tuple_params<T>(conn:&Connection, sql:&str, params:&[&dyn ToSql]) -> Result<Option<T>, rusqlite::Error> 
+where
+    for<'r> T: TryFrom<
+        &'r Row<'r>, 
+        Error = Error 
+    >
+{
+    let mut stmt = conn.prepare(sql)?;
+    let mut rows = stmt.query(params)?;
+    match rows.next()? {
+        Some(row) => Ok(Some(T::try_from(row)?)),
+        None => Ok(None),
+    }
+}
+
+/// as query_to_i64, but binds `params` to the placeholders in `sql`. None if no rows, error on NULL or failed cast
+pub fn query_to_i64_params(conn:&Connection, sql:&str, params:&[&dyn ToSql]) -> Result<Option<i64>, rusqlite::Error> {
+    conn.query_row(sql, params, |row| value_ref_to_i64(row.get_ref(0)?)).optional()
+}
+
+/// as query_to_string, but binds `params` to the placeholders in `sql`. None if NULL, error on no rows
+pub fn query_to_string_params(conn:&Connection, sql:&str, params:&[&dyn ToSql]) -> Result<Option<String>, rusqlite::Error> {
+    conn.query_row(sql, params, |row| Ok(value_ref_to_string(row.get_ref(0)?)))
+}
+
+/// returns `SELECT COUNT(*) FROM <table>`, optionally filtered by `where_clause` (the condition without the `WHERE` keyword,
+/// e.g. built with `dbfmt_comp`). The table name is quoted; the where clause is used as is, so must not contain untrusted input
+///
+/// # Example
+/// `count_rows(&conn, "t", Some(&format!("c{}", dbfmt_comp(Some(3), CompOp::Gt))))` -> `SELECT COUNT(*) FROM "t" WHERE c > 3`
+pub fn count_rows(conn:&Connection, table:&str, where_clause:Option<&str>) -> Result<i64, HelperError> {
+    let mut sql = format!("SELECT COUNT(*) FROM {}", quote_identifier(table));
+    if let Some(where_clause) = where_clause.map(str::trim).filter(|w| !w.is_empty()) {
+        sql.push_str(" WHERE ");
+        sql.push_str(where_clause);
+    }
+    let count: i64 = conn.query_row(&sql, [], |row| row.get(0))?;
+    Ok(count)
+}
+
+/// snapshots the main database of `conn` (file or in-memory) to `dest` using sqlite's online backup api, overwriting `dest`.
+/// Copies 100 pages per step, pausing between steps so other connections can keep using the source database.
+pub fn backup_to(conn:&Connection, dest:&Path) -> Result<(), HelperError> {
+    let mut dest_conn = Connection::open(dest)?;
+    let backup = Backup::new(conn, &mut dest_conn)?;
+    backup.run_to_completion(100, Duration::from_millis(10), None)?;
+    Ok(())
+}
+
+/// reads column 0 as the key and column 1 as the value of each row, e.g. to load a settings table in one call.
+/// If a key repeats, the last row wins
+pub fn query_to_hashmap<K, V>(conn:&Connection, sql:&str) -> Result<HashMap<K, V>, rusqlite::Error>
+where
+    K: FromSql + Eq + Hash,
+    V: FromSql,
+{
+    let mut stmt = conn.prepare(sql)?;
+    let rows_result = stmt.query_map([], |row| Ok((row.get::<_, K>(0)?, row.get::<_, V>(1)?)))?;
+    rows_result.collect()
+}
+
+
+/// returns the first column of the first row as any FromSql type, or None if there are no rows.
+/// A NULL errors unless `T` is an Option, e.g. `query_single_value::<Option<f64>>(&conn, "SELECT MAX(x) FROM t")`
+pub fn query_single_value<T: FromSql>(conn:&Connection, sql:&str) -> Result<Option<T>, rusqlite::Error> {
+    conn.query_row(sql, [], |row| row.get(0)).optional()
+}
+
+/// runs `DELETE FROM <table> WHERE <where_clause>` and returns the rows removed. The where clause (without the `WHERE`
+/// keyword, e.g. built with `where_sql!`) is required, so a blank one errors rather than emptying the table. As with
+/// count_rows the table name is quoted and the where clause used as is
+///
+/// # Example
+/// `delete_rows(&conn, "t", &where_sql!("{} AND {}", ("c", dbfmt_comp(Some(3), CompOp::Lt)), ("d", dbfmt_comp::<i64>(None, CompOp::Eq))))`
+/// -> `DELETE FROM "t" WHERE c < 3 AND d IS NULL`
+pub fn delete_rows(conn:&Connection, table:&str, where_clause:&str) -> Result<usize, HelperError> {
+    let where_clause = where_clause.trim();
+    if where_clause.is_empty() {
+        return Err(HelperError::InvalidInput(format!("delete_rows: a where clause is required to delete from {}", table)));
+    }
+    let sql = format!("DELETE FROM {} WHERE {}", quote_identifier(table), where_clause);
+    Ok(conn.execute(&sql, [])?)
+}
+
+/// cells longer than this many characters are cut short with `…` by print_table
+const PRINT_TABLE_MAX_CELL_WIDTH: usize = 40;
+
+/// writes the result of `sql` to `writer` as a table for reading in a terminal: column names, a separator, then one line per row,
+/// with columns padded to line up. Values are formatted as in query_to_string, NULL as `NULL`, and cells over 40 characters
+/// are truncated. e.g.
+/// ```text
+/// id | name
+/// ---+------
+/// 1  | apple
+/// ```
+pub fn print_table(conn:&Connection, sql:&str, writer:&mut dyn Write) -> Result<(), HelperError> {
+    let mut stmt = conn.prepare(sql)?;
+    let column_count = stmt.column_count();
+    let header: Vec<String> = stmt.column_names().iter()
+        .map(|name| truncate_with_ellipsis(name, PRINT_TABLE_MAX_CELL_WIDTH))
+        .collect();
+
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut query_rows = stmt.query([])?;
+    while let Some(row) = query_rows.next()? {
+        let mut cells: Vec<String> = Vec::with_capacity(column_count);
+        for icol in 0..column_count {
+            let value = value_ref_to_string(row.get_ref(icol)?).unwrap_or_else(|| String::from("NULL"));
+            cells.push(truncate_with_ellipsis(&value, PRINT_TABLE_MAX_CELL_WIDTH));
+        }
+        rows.push(cells);
+    }
+
+    let mut widths: Vec<usize> = header.iter().map(|cell| count_graphemes(cell)).collect();
+    for cells in &rows {
+        for (width, cell) in widths.iter_mut().zip(cells) {
+            *width = (*width).max(count_graphemes(cell));
+        }
+    }
+
+    // the last column isn't padded, so lines carry no trailing spaces
+    let format_line = |cells: &[String]| -> String {
+        cells.iter().zip(&widths).enumerate()
+            .map(|(icol, (cell, width))| {
+                if icol + 1 == cells.len() {
+                    cell.clone()
+                } else {
+                    format!("{}{}", cell, " ".repeat(width - count_graphemes(cell)))
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(" | ")
+    };
+    writeln!(writer, "{}", format_line(&header))?;
+    let separator = widths.iter().map(|width| "-".repeat(*width)).collect::<Vec<String>>().join("-+-");
+    writeln!(writer, "{}", separator)?;
+    for cells in &rows {
+        writeln!(writer, "{}", format_line(cells))?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
 
 #[cfg(test)]
 #[path = "./tests/sql_tests.rs"]
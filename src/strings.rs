@@ -1,15 +1,365 @@
-﻿pub fn get_last_n_chars(s: &str, n: usize) -> String {
+﻿use unicode_segmentation::UnicodeSegmentation;
+
+pub fn get_last_n_chars(s: &str, n: usize) -> String {
     s.chars().rev().take(n).collect::<String>().chars().rev().collect()
 }
 
+/// the number of user-perceived characters (extended grapheme clusters) in `s`, e.g. `"e\u{301}"` (e + combining accent) is 1
+pub fn count_graphemes(s: &str) -> usize {
+    s.graphemes(true).count()
+}
+
+/// `s` if it has at most `max_graphemes` graphemes, otherwise its first `max_graphemes - 1` followed by `…`,
+/// so the result is never longer than `max_graphemes`
+pub fn truncate_with_ellipsis(s: &str, max_graphemes: usize) -> String {
+    if count_graphemes(s) <= max_graphemes {
+        return s.to_string();
+    }
+    if max_graphemes == 0 {
+        return String::new();
+    }
+    let mut truncated: String = s.graphemes(true).take(max_graphemes - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+/// a count shortened with 1000-based suffixes and one decimal, dropping a trailing `.0`: 999 -> `"999"`, 1_500 -> `"1.5K"`,
+/// 3_400_000 -> `"3.4M"`, 5_000_000_000 -> `"5B"`, then `T`. See paths::format_bytes for 1024-based byte sizes
+pub fn human_readable_count(n: u64) -> String {
+    const SUFFIXES: [&str; 5] = ["", "K", "M", "B", "T"];
+    if n < 1_000 {
+        return n.to_string();
+    }
+    let mut value = n as f64;
+    let mut isuffix = 0;
+    // step up while the value would round to 1000.0 or more, so 999_950 is "1M" rather than "1000K"
+    while isuffix < SUFFIXES.len() - 1 && (value * 10.0).round() >= 10_000.0 {
+        value /= 1_000.0;
+        isuffix += 1;
+    }
+    let formatted = format!("{:.1}", value);
+    let formatted = formatted.strip_suffix(".0").unwrap_or(&formatted);
+    format!("{}{}", formatted, SUFFIXES[isuffix])
+}
+
+/// uppercases the first character of each whitespace-separated word and lowercases the rest, keeping the original spacing,
+/// e.g. `"JOHN  SMITH"` -> `"John  Smith"`. Unlike a title caser there are no small words kept lowercase, and only whitespace
+/// starts a word, so `"MARY-JANE"` -> `"Mary-jane"`
+pub fn capitalize_words(s: &str) -> String {
+    let mut capitalized = String::with_capacity(s.len());
+    let mut at_word_start = true;
+    for c in s.chars() {
+        if c.is_whitespace() {
+            capitalized.push(c);
+            at_word_start = true;
+        } else if at_word_start {
+            capitalized.extend(c.to_uppercase());
+            at_word_start = false;
+        } else {
+            capitalized.extend(c.to_lowercase());
+        }
+    }
+    capitalized
+}
+
+/// splits an identifier into its words: on `_`, `-` and whitespace, and where the case changes, so `"userName"`,
+/// `"user_name"` and `"UserName"` all give `["user", "name"]`. An acronym stays one word: `"HTTPServer"` -> `["http", "server"]`.
+/// Words are lowercased
+fn split_case_words(s: &str) -> Vec<String> {
+    let chars: Vec<char> = s.chars().collect();
+    let mut words: Vec<String> = Vec::new();
+    let mut word = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c == '_' || c == '-' || c.is_whitespace() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+        if c.is_uppercase() && !word.is_empty() {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+            // "userName" splits before N, "HTTPServer" before S
+            if prev.is_lowercase() || prev.is_ascii_digit() || (prev.is_uppercase() && next_is_lower) {
+                words.push(std::mem::take(&mut word));
+            }
+        }
+        word.extend(c.to_lowercase());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// uppercases the first character of an already lowercase word
+fn capitalize_first(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// joins the words of `s` (split on `_`, `-`, spaces and case changes) as camelCase: `"user_name"` -> `"userName"`.
+/// Already camelCase input is returned unchanged
+pub fn to_camel_case(s: &str) -> String {
+    split_case_words(s).iter()
+        .enumerate()
+        .map(|(i, word)| if i == 0 { word.clone() } else { capitalize_first(word) })
+        .collect()
+}
+
+/// as to_camel_case, but PascalCase, with the first word capitalized too: `"user_name"` -> `"UserName"`
+pub fn to_pascal_case(s: &str) -> String {
+    split_case_words(s).iter().map(|word| capitalize_first(word)).collect()
+}
+
+/// the number of words in `s`, i.e. runs of non-whitespace, so repeated or surrounding whitespace doesn't add to the count
+pub fn count_words(s: &str) -> usize {
+    s.split_whitespace().count()
+}
+
+/// the longest run of characters (unicode scalar values) found in both `a` and `b`, empty if they share none.
+/// Of equally long runs, the one that occurs first in `a` is returned. Takes time proportional to `a.len() * b.len()`
+pub fn longest_common_substring(a: &str, b: &str) -> String {
+    let a_chars: Vec<char> = a.chars().collect();
+    let b_chars: Vec<char> = b.chars().collect();
+    // run_lengths[j + 1] is the length of the common run ending at the current char of a and at b_chars[j]
+    let mut run_lengths: Vec<usize> = vec![0; b_chars.len() + 1];
+    let mut best_len: usize = 0;
+    let mut best_end: usize = 0;
+    for (i, a_char) in a_chars.iter().enumerate() {
+        // go backwards so run_lengths[j] still holds the previous char of a's value
+        for j in (0..b_chars.len()).rev() {
+            if *a_char == b_chars[j] {
+                run_lengths[j + 1] = run_lengths[j] + 1;
+                // strictly longer only, so the first run found (ending earliest in a) wins ties
+                if run_lengths[j + 1] > best_len {
+                    best_len = run_lengths[j + 1];
+                    best_end = i + 1;
+                }
+            } else {
+                run_lengths[j + 1] = 0;
+            }
+        }
+    }
+    a_chars[best_end - best_len..best_end].iter().collect()
+}
+
+/// `unit` repeated `times` times with `sep` between each, e.g. sql placeholders: `repeat_with_separator("?", 3, ", ")` -> `"?, ?, ?"`
+pub fn repeat_with_separator(unit: &str, times: usize, sep: &str) -> String {
+    std::iter::repeat_n(unit, times).collect::<Vec<&str>>().join(sep)
+}
+
+/// as `str::strip_prefix`, but ignoring ASCII case: `strip_prefix_ci("HTTPS://example.com", "https://")` -> `Some("example.com")`
+pub fn strip_prefix_ci<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = s.get(..prefix.len())?;
+    if head.eq_ignore_ascii_case(prefix) {
+        s.get(prefix.len()..)
+    } else {
+        None
+    }
+}
+
+/// as `str::strip_suffix`, but ignoring ASCII case: `strip_suffix_ci("photo.JPG", ".jpg")` -> `Some("photo")`
+pub fn strip_suffix_ci<'a>(s: &'a str, suffix: &str) -> Option<&'a str> {
+    let split = s.len().checked_sub(suffix.len())?;
+    let tail = s.get(split..)?;
+    if tail.eq_ignore_ascii_case(suffix) {
+        s.get(..split)
+    } else {
+        None
+    }
+}
+
+/// the Emoji_Presentation ranges from Unicode 16's emoji-data.txt, sorted: characters shown as emoji by default. Text
+/// symbols that only become emoji with U+FE0F after them, like ✓ ★ ☐ or the dingbat arrows, are not in it
+const EMOJI_PRESENTATION: &[(u32, u32)] = &[
+    (0x231A, 0x231B), (0x23E9, 0x23EC), (0x23F0, 0x23F0), (0x23F3, 0x23F3), (0x25FD, 0x25FE), (0x2614, 0x2615),
+    (0x2648, 0x2653), (0x267F, 0x267F), (0x2693, 0x2693), (0x26A1, 0x26A1), (0x26AA, 0x26AB), (0x26BD, 0x26BE),
+    (0x26C4, 0x26C5), (0x26CE, 0x26CE), (0x26D4, 0x26D4), (0x26EA, 0x26EA), (0x26F2, 0x26F3), (0x26F5, 0x26F5),
+    (0x26FA, 0x26FA), (0x26FD, 0x26FD), (0x2705, 0x2705), (0x270A, 0x270B), (0x2728, 0x2728), (0x274C, 0x274C),
+    (0x274E, 0x274E), (0x2753, 0x2755), (0x2757, 0x2757), (0x2795, 0x2797), (0x27B0, 0x27B0), (0x27BF, 0x27BF),
+    (0x2B1B, 0x2B1C), (0x2B50, 0x2B50), (0x2B55, 0x2B55), (0x1F004, 0x1F004), (0x1F0CF, 0x1F0CF), (0x1F18E, 0x1F18E),
+    (0x1F191, 0x1F19A), (0x1F1E6, 0x1F1FF), (0x1F201, 0x1F201), (0x1F21A, 0x1F21A), (0x1F22F, 0x1F22F), (0x1F232, 0x1F236),
+    (0x1F238, 0x1F23A), (0x1F250, 0x1F251), (0x1F300, 0x1F320), (0x1F32D, 0x1F335), (0x1F337, 0x1F37C), (0x1F37E, 0x1F393),
+    (0x1F3A0, 0x1F3CA), (0x1F3CF, 0x1F3D3), (0x1F3E0, 0x1F3F0), (0x1F3F4, 0x1F3F4), (0x1F3F8, 0x1F43E), (0x1F440, 0x1F440),
+    (0x1F442, 0x1F4FC), (0x1F4FF, 0x1F53D), (0x1F54B, 0x1F54E), (0x1F550, 0x1F567), (0x1F57A, 0x1F57A), (0x1F595, 0x1F596),
+    (0x1F5A4, 0x1F5A4), (0x1F5FB, 0x1F64F), (0x1F680, 0x1F6C5), (0x1F6CC, 0x1F6CC), (0x1F6D0, 0x1F6D2), (0x1F6D5, 0x1F6D7),
+    (0x1F6DC, 0x1F6DF), (0x1F6EB, 0x1F6EC), (0x1F6F4, 0x1F6FC), (0x1F7E0, 0x1F7EB), (0x1F7F0, 0x1F7F0), (0x1F90C, 0x1F93A),
+    (0x1F93C, 0x1F945), (0x1F947, 0x1F9FF), (0x1FA70, 0x1FA7C), (0x1FA80, 0x1FA89), (0x1FA8F, 0x1FAC6), (0x1FACE, 0x1FADC),
+    (0x1FADF, 0x1FAE9), (0x1FAF0, 0x1FAF8),
+];
+
+/// true for a char that makes its grapheme an emoji: an Emoji_Presentation character (pictographs, flags as regional
+/// indicators, skin tones), or the emoji presentation selector / keycap / tag marks
+fn is_emoji_char(c: char) -> bool {
+    let c = c as u32;
+    matches!(c,
+        0xFE0F // emoji presentation selector
+        | 0x20E3 // combining keycap
+        | 0xE0020..=0xE007F // tags, as in subdivision flags
+    ) || {
+        // the first range not wholly below c
+        let i = EMOJI_PRESENTATION.partition_point(|&(_, hi)| hi < c);
+        EMOJI_PRESENTATION.get(i).is_some_and(|&(lo, _)| lo <= c)
+    }
+}
+
+/// removes emoji from `s`, leaving other text (including its spacing) as is. Works per grapheme cluster, so a whole ZWJ
+/// sequence such as a family, a flag, or a keycap like `1️⃣` goes at once, while zero width joiners in other scripts are kept.
+/// Only characters shown as emoji by default count, so text symbols like `©`, `✓` or `★` stay unless written with the
+/// emoji presentation selector
+pub fn remove_emoji(s: &str) -> String {
+    s.graphemes(true)
+        .filter(|grapheme| !grapheme.chars().any(is_emoji_char))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::needless_borrow)]
     fn test_get_last_n_chars() {
         let input: &str = "The customer's order";
         assert_eq!(get_last_n_chars(&input, 3), "der");
     }
 
+    #[test]
+    fn test_count_graphemes() {
+        assert_eq!(count_graphemes("abc"), 3);
+        assert_eq!(count_graphemes("e\u{301}te\u{301}"), 3);
+        assert_eq!(count_graphemes("🇳🇿"), 1);
+        assert_eq!(count_graphemes(""), 0);
+    }
+
+    #[test]
+    fn test_human_readable_count() {
+        assert_eq!(human_readable_count(0), "0");
+        assert_eq!(human_readable_count(999), "999");
+        assert_eq!(human_readable_count(1_000), "1K");
+        assert_eq!(human_readable_count(1_234), "1.2K");
+        assert_eq!(human_readable_count(999_950), "1M");
+        assert_eq!(human_readable_count(1_500_000), "1.5M");
+        assert_eq!(human_readable_count(5_000_000_000), "5B");
+        assert_eq!(human_readable_count(7_260_000_000_000), "7.3T");
+        assert_eq!(human_readable_count(u64::MAX), "18446744.1T");
+    }
+
+    #[test]
+    fn test_capitalize_words() {
+        assert_eq!(capitalize_words("JOHN SMITH"), "John Smith");
+        assert_eq!(capitalize_words("  jOHN\tsmith  "), "  John\tSmith  ");
+        assert_eq!(capitalize_words("ÉMILE ZOLA"), "Émile Zola");
+        assert_eq!(capitalize_words(""), "");
+    }
+
+    #[test]
+    fn test_capitalize_words_hyphenated() {
+        //only whitespace starts a word
+        assert_eq!(capitalize_words("MARY-JANE O'NEIL"), "Mary-jane O'neil");
+    }
+
+    #[test]
+    fn test_to_camel_case_and_pascal_case() {
+        assert_eq!(to_camel_case("user_name"), "userName");
+        assert_eq!(to_pascal_case("user_name"), "UserName");
+        assert_eq!(to_camel_case("first-name last"), "firstNameLast");
+        assert_eq!(to_pascal_case("USER_ID"), "UserId");
+        assert_eq!(to_camel_case(""), "");
+    }
+
+    #[test]
+    fn test_to_camel_case_already_cased() {
+        assert_eq!(to_camel_case("userName"), "userName");
+        assert_eq!(to_camel_case("UserName"), "userName");
+        assert_eq!(to_pascal_case("userNameId"), "UserNameId");
+        assert_eq!(to_camel_case("HTTPServer_port"), "httpServerPort");
+        assert_eq!(to_camel_case("utf8Value"), "utf8Value");
+    }
+
+    #[test]
+    fn test_count_words() {
+        assert_eq!(count_words("the quick brown fox"), 4);
+        assert_eq!(count_words("  leading and trailing  "), 3);
+        assert_eq!(count_words("many    internal \t\n spaces"), 3);
+        assert_eq!(count_words(""), 0);
+        assert_eq!(count_words("   "), 0);
+    }
+
+    #[test]
+    fn test_longest_common_substring() {
+        assert_eq!(longest_common_substring("The Matrix Reloaded", "Matrix Revolutions"), "Matrix Re");
+        assert_eq!(longest_common_substring("café crème", "crème brûlée"), "crème");
+        assert_eq!(longest_common_substring("abc", "xyz"), "");
+        assert_eq!(longest_common_substring("", "xyz"), "");
+        assert_eq!(longest_common_substring("same title", "same title"), "same title");
+    }
+
+    #[test]
+    fn test_longest_common_substring_tie_break() {
+        //"ab" and "cd" are both length 2; "cd" comes first in a
+        assert_eq!(longest_common_substring("cd-ab", "ab-cd"), "cd");
+    }
+
+    #[test]
+    fn test_repeat_with_separator() {
+        assert_eq!(repeat_with_separator("?", 3, ", "), "?, ?, ?");
+        assert_eq!(repeat_with_separator("(?, ?)", 2, ","), "(?, ?),(?, ?)");
+        assert_eq!(repeat_with_separator("?", 1, ", "), "?");
+        assert_eq!(repeat_with_separator("?", 0, ", "), "");
+    }
+
+    #[test]
+    fn test_strip_prefix_ci() {
+        assert_eq!(strip_prefix_ci("HTTPS://example.com", "https://"), Some("example.com"));
+        assert_eq!(strip_prefix_ci("https://example.com", "https://"), Some("example.com"));
+        assert_eq!(strip_prefix_ci("http://example.com", "https://"), None);
+        assert_eq!(strip_prefix_ci("ht", "https://"), None);
+        //a prefix length that splits a multi-byte char is a non-match, not a panic
+        assert_eq!(strip_prefix_ci("éa", "x"), None);
+    }
+
+    #[test]
+    fn test_strip_suffix_ci() {
+        assert_eq!(strip_suffix_ci("photo.JPG", ".jpg"), Some("photo"));
+        assert_eq!(strip_suffix_ci("photo.png", ".jpg"), None);
+        assert_eq!(strip_suffix_ci("g", ".jpg"), None);
+        assert_eq!(strip_suffix_ci("aé", "x"), None);
+    }
+
+    #[test]
+    fn test_remove_emoji() {
+        assert_eq!(remove_emoji("Kia ora 🇳🇿!"), "Kia ora !");
+        //man, woman, girl, boy joined by zero width joiners
+        assert_eq!(remove_emoji("family: 👨\u{200D}👩\u{200D}👧\u{200D}👦."), "family: .");
+        assert_eq!(remove_emoji("👍🏽 ok ❤️ 1️⃣"), " ok  ");
+    }
+
+    #[test]
+    fn test_remove_emoji_plain_text_unchanged() {
+        let plain = "Café © 2024 – naïve résumé, 中文, क्‍ष \t end";
+        assert_eq!(remove_emoji(plain), plain);
+        assert_eq!(remove_emoji(""), "");
+    }
+
+    #[test]
+    fn test_remove_emoji_keeps_text_symbols() {
+        let symbols = "✓ ✗ ★ ☐ ☺ ♥ ➔ ↑ 🄰";
+        assert_eq!(remove_emoji(symbols), symbols);
+        //the same symbols as emoji
+        assert_eq!(remove_emoji("✅ ❌ ⭐ ☺\u{FE0F} ♥\u{FE0F}"), "    ");
+    }
+
+    #[test]
+    fn test_truncate_with_ellipsis() {
+        assert_eq!(truncate_with_ellipsis("hello world", 5), "hell…");
+        assert_eq!(truncate_with_ellipsis("hello", 5), "hello");
+        assert_eq!(truncate_with_ellipsis("e\u{301}e\u{301}e\u{301}", 2), "e\u{301}…");
+        assert_eq!(truncate_with_ellipsis("hello", 0), "");
+    }
+
 }
\ No newline at end of file
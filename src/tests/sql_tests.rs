@@ -70,6 +70,30 @@ fn test_bare_i32() {
     assert_eq!(dbfmt_t(&input), "-500");
 }
 
+#[test]
+fn test_bare_f64() {
+    let input: f64 = 49.99;
+    assert_eq!(dbfmt_t(&input), "49.99");
+}
+
+#[test]
+fn test_bare_f64_nan() {
+    assert_eq!(dbfmt_t(&f64::NAN), "NULL");
+    assert_eq!(dbfmt(Some(f32::NAN)), "NULL");
+    assert_eq!(dbfmt_comp(Some(f64::NAN), CompOp::Eq), " IS NULL");
+}
+
+#[test]
+fn test_bare_f64_infinity() {
+    assert_eq!(dbfmt_t(&f64::INFINITY), "9.0e+999");
+    assert_eq!(dbfmt_t(&f64::NEG_INFINITY), "-9.0e+999");
+    assert_eq!(dbfmt_comp(Some(f32::INFINITY), CompOp::Lt), " < 9.0e+999");
+    //sqlite must read the sentinel back as an infinite REAL
+    let sql = format!("SELECT {}, {};", dbfmt_t(&f64::INFINITY), dbfmt_t(&f64::NEG_INFINITY));
+    let result = query_single_row_to_tuple::<(f64,f64)>(Path::new(""), &sql).unwrap();
+    assert_eq!(result, Some((f64::INFINITY, f64::NEG_INFINITY)));
+}
+
 #[test]
 fn test_bare_custom_type_display() {
     let input: CustomType = CustomType { id: 123 };
@@ -89,6 +113,92 @@ fn test_bare_datetime_local() {
     assert_eq!(dbfmt_t(&input), "datetime('2023-12-25 14:30:45', 'utc')");
 }
 
+#[test]
+fn test_bare_naive_time() {
+    let input = chrono::NaiveTime::from_hms_opt(9, 30, 0).unwrap();
+    assert_eq!(dbfmt_t(&input), "time('09:30:00')");
+    assert_eq!(dbfmt_comp(Some(input), CompOp::GtEq), " >= time('09:30:00')");
+    let with_millis = chrono::NaiveTime::from_hms_milli_opt(23, 5, 9, 250).unwrap();
+    assert_eq!(dbfmt(Some(with_millis)), "time('23:05:09')");
+}
+
+#[test]
+fn test_naive_time_round_trip() {
+    let input = chrono::NaiveTime::from_hms_opt(9, 30, 0).unwrap();
+    let sql = format!("SELECT {};", dbfmt_t(&input));
+    let result = query_single_row_to_tuple::<(chrono::NaiveTime,)>(Path::new(""), &sql).unwrap();
+    assert_eq!(result, Some((input,)));
+}
+
+#[derive(Debug, Clone, Copy)]
+#[repr(i64)]
+enum Priority {
+    Low = 1,
+    High = 10,
+    Unset = -1,
+}
+
+impl From<Priority> for i64 {
+    fn from(priority: Priority) -> i64 {
+        priority as i64
+    }
+}
+
+impl Display for Priority {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(f, "{:?} priority", self)
+    }
+}
+
+#[test]
+fn test_dbfmt_enum_repr() {
+    assert_eq!(dbfmt_enum_repr(Priority::Low), "1");
+    assert_eq!(dbfmt_enum_repr(Priority::High), "10");
+    assert_eq!(dbfmt_enum_repr(Priority::Unset), "-1");
+    //not the Display text dbfmt_t would give
+    assert_eq!(dbfmt_t(&Priority::High), "High priority");
+}
+
+#[test]
+fn test_bare_datetime_utc_precise() {
+    let input: DateTime<Utc> = Utc.with_ymd_and_hms(2023, 12, 25, 14, 30, 45).unwrap() + chrono::Duration::milliseconds(123);
+    assert_eq!(dbfmt_t_precise(&input), "datetime('2023-12-25 14:30:45.123', 'subsec')");
+    assert_eq!(dbfmt_precise(Some(input)), "datetime('2023-12-25 14:30:45.123', 'subsec')");
+    assert_eq!(dbfmt_precise::<DateTime<Utc>>(None), "NULL");
+    assert_eq!(dbfmt_t_precise(&42), "42");
+}
+
+#[test]
+fn test_bare_datetime_local_precise() {
+    let input: DateTime<Local> = Local.with_ymd_and_hms(2023, 12, 25, 14, 30, 45).unwrap() + chrono::Duration::milliseconds(7);
+    assert_eq!(dbfmt_t_precise(&input), "datetime('2023-12-25 14:30:45.007', 'utc', 'subsec')");
+}
+
+#[test]
+fn test_datetime_precise_round_trip() {
+    let input: DateTime<Utc> = Utc.with_ymd_and_hms(2023, 12, 25, 14, 30, 45).unwrap() + chrono::Duration::milliseconds(123);
+    let sql = format!("SELECT {};", dbfmt_t_precise(&input));
+    let result = query_single_row_to_tuple::<(DateTime<Utc>,)>(Path::new(""), &sql).unwrap();
+    assert_eq!(result, Some((input,)));
+    assert_eq!(result.unwrap().0.timestamp_subsec_millis(), 123);
+}
+
+#[test]
+fn test_datetime_local_precise_round_trip() {
+    let input: DateTime<Local> = Local.with_ymd_and_hms(2023, 12, 25, 14, 30, 45).unwrap() + chrono::Duration::milliseconds(456);
+    let sql = format!("SELECT {};", dbfmt_t_precise(&input));
+    let result = query_single_row_to_tuple::<(DateTime<Local>,)>(Path::new(""), &sql).unwrap();
+    assert_eq!(result, Some((input,)));
+}
+
+#[cfg(feature = "uuid")]
+#[test]
+fn test_bare_uuid() {
+    let input = uuid::Uuid::parse_str("67E55044-10B1-426F-9247-BB680E5FE0C8").unwrap();
+    assert_eq!(dbfmt_t(&input), "'67e55044-10b1-426f-9247-bb680e5fe0c8'");
+    assert_eq!(dbfmt_comp(Some(input), CompOp::Eq), " = '67e55044-10b1-426f-9247-bb680e5fe0c8'");
+}
+
 // MACRO tests
 
 #[test]
@@ -186,6 +296,34 @@ fn test_query_to_i64_null() {
     assert!(result.is_err());
 }
 
+#[test]
+fn test_query_to_i64_or_empty_sum() {
+    let dbfilepath = std::env::temp_dir().join("helper_lib_test_i64_or_sum.db");
+    execute_batch(&dbfilepath, "CREATE TABLE IF NOT EXISTS t(x INTEGER); DELETE FROM t;").unwrap();
+    let result = query_to_i64_or(&dbfilepath, "SELECT SUM(x) FROM t;", 0).unwrap();
+    //clean up temp db file
+    if dbfilepath.exists() {
+        fs::remove_file(dbfilepath).unwrap();
+    }
+    assert_eq!(result, 0);
+}
+
+#[test]
+fn test_query_to_i64_or_no_rows() {
+    let dbfilepath = PathBuf::from("./tests/resources/test.db");
+    let sql = "SELECT c FROM t WHERE 1=2;";
+    let result = query_to_i64_or(&dbfilepath, sql, -1).unwrap();
+    assert_eq!(result, -1);
+}
+
+#[test]
+fn test_query_to_i64_or_value() {
+    let dbfilepath = PathBuf::from("./tests/resources/test.db");
+    let sql = "SELECT COUNT(*) FROM t;";
+    let result = query_to_i64_or(&dbfilepath, sql, -1).unwrap();
+    assert_eq!(result, 3);
+}
+
 #[test]
 fn test_query_single_row_to_tuple() {
     let dbfilepath = PathBuf::from("./tests/resources/test.db");
@@ -231,6 +369,7 @@ fn test_query_single_row_to_tuple_no_rows() {
 }
 
 #[test]
+#[allow(clippy::vec_init_then_push)]
 fn test_query_to_tuples() {
     let dbfilepath = PathBuf::from("./tests/resources/test.db");
     let sql = "SELECT c, 0 AS c2 FROM t LIMIT 2;";
@@ -241,12 +380,48 @@ fn test_query_to_tuples() {
     assert_eq!(result, expected);
 }
 
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_query_to_tuples_async() {
+    let dbfilepath = PathBuf::from("./tests/resources/test.db");
+    let sql = "SELECT c, 0 AS c2 FROM t LIMIT 3;";
+    let result = query_to_tuples_async::<(Option<i64>,u8)>(&dbfilepath, sql).await.unwrap();
+    let expected = query_to_tuples::<(Option<i64>,u8)>(&dbfilepath, sql).unwrap();
+    assert_eq!(result, expected);
+    assert!(query_to_tuples_async::<(i64,)>(&dbfilepath, "SELECT missing FROM t").await.is_err());
+}
+
+#[cfg(feature = "pool")]
+#[test]
+fn test_sqlite_pool_threads() {
+    let dbfilepath = std::env::temp_dir().join("helper_lib_test_sqlite_pool.db");
+    _ = fs::remove_file(&dbfilepath);
+    let pool = SqlitePool::new(&dbfilepath, 3).unwrap();
+    pool.get().unwrap().execute_batch("CREATE TABLE t(c INTEGER); INSERT INTO t VALUES (1), (2), (3);").unwrap();
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..6)
+            .map(|i| {
+                let pool = &pool;
+                scope.spawn(move || {
+                    let conn = pool.get().unwrap();
+                    query_to_tuples_conn::<(i64,)>(&conn, &format!("SELECT SUM(c) + {} FROM t", i)).unwrap()
+                })
+            })
+            .collect();
+        let sums: Vec<Vec<(i64,)>> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(sums, (0..6).map(|i| vec![(6 + i,)]).collect::<Vec<_>>());
+    });
+    drop(pool);
+    _ = fs::remove_file(&dbfilepath);
+}
+
 #[test]
+#[allow(clippy::vec_init_then_push)]
 fn test_query_to_tuples_conn() {
     let dbfilepath = PathBuf::from("./tests/resources/test.db");
     let conn = Connection::open(&dbfilepath).unwrap();
     let sql = "SELECT c, 0 AS c2 FROM t LIMIT 2;";
-    let result = query_to_tuples_conn::<(i64,u8)>(conn, sql).unwrap();
+    let result = query_to_tuples_conn::<(i64,u8)>(&conn, sql).unwrap();
     let mut expected: Vec<(i64,u8)> = Vec::new();
     expected.push((1,0));
     expected.push((2,0));
@@ -254,6 +429,7 @@ fn test_query_to_tuples_conn() {
 }
 
 #[test]
+#[allow(clippy::vec_init_then_push)]
 fn test_query_to_tuples_nullable() {
     let dbfilepath = PathBuf::from("./tests/resources/test.db");
     let sql = "SELECT c, 0 AS c2 FROM t LIMIT 3;";
@@ -319,6 +495,7 @@ fn test_query_to_string_inmemory() {
 }
 
 #[test]
+#[allow(clippy::let_unit_value)]
 fn test_execute_batch() {
     let sql = "CREATE TABLE t(c); INSERT INTO t VALUES (2); DELETE FROM t;";
     let result = execute_batch(Path::new(""), sql).unwrap();
@@ -344,3 +521,528 @@ fn test_execute_return_changed_rows() {
     }
     assert_eq!(result, 2);
 }
+
+#[test]
+fn test_table_exists() {
+    let dbfilepath = std::env::temp_dir().join("helper_lib_test_table_exists.db");
+    execute_batch(&dbfilepath, "CREATE TABLE IF NOT EXISTS known(id INTEGER, name TEXT);").unwrap();
+    let conn = Connection::open(&dbfilepath).unwrap();
+    let exists = table_exists(&conn, "known").unwrap();
+    let missing = table_exists(&conn, "unknown").unwrap();
+    let injected = table_exists(&conn, "known' OR '1'='1").unwrap();
+    drop(conn);
+    //clean up temp db file
+    if dbfilepath.exists() {
+        fs::remove_file(dbfilepath).unwrap();
+    }
+    assert!(exists);
+    assert!(!missing);
+    assert!(!injected);
+}
+
+#[test]
+fn test_column_exists() {
+    let dbfilepath = std::env::temp_dir().join("helper_lib_test_column_exists.db");
+    execute_batch(&dbfilepath, "CREATE TABLE IF NOT EXISTS known(id INTEGER, name TEXT);").unwrap();
+    let conn = Connection::open(&dbfilepath).unwrap();
+    let exists = column_exists(&conn, "known", "name").unwrap();
+    let missing_column = column_exists(&conn, "known", "age").unwrap();
+    let missing_table = column_exists(&conn, "unknown", "name").unwrap();
+    drop(conn);
+    //clean up temp db file
+    if dbfilepath.exists() {
+        fs::remove_file(dbfilepath).unwrap();
+    }
+    assert!(exists);
+    assert!(!missing_column);
+    assert!(!missing_table);
+}
+
+#[test]
+fn test_run_migrations_idempotent() {
+    let conn = Connection::open_in_memory().unwrap();
+    let migrations = [
+        ("001_create_t", "CREATE TABLE t(c INTEGER);"),
+        ("002_seed_t", "INSERT INTO t VALUES (1); INSERT INTO t VALUES (2);"),
+    ];
+    run_migrations(&conn, &migrations).unwrap();
+    //second run must not re-apply the seed
+    run_migrations(&conn, &migrations).unwrap();
+    let rows: i64 = conn.query_row("SELECT COUNT(*) FROM t", [], |row| row.get(0)).unwrap();
+    let applied: i64 = conn.query_row("SELECT COUNT(*) FROM _migrations", [], |row| row.get(0)).unwrap();
+    assert_eq!(rows, 2);
+    assert_eq!(applied, 2);
+}
+
+#[test]
+fn test_run_migrations_partial_failure_rollback() {
+    let conn = Connection::open_in_memory().unwrap();
+    let migrations = [
+        ("001_create_t", "CREATE TABLE t(c INTEGER);"),
+        ("002_broken", "INSERT INTO t VALUES (1); INSERT INTO missing_table VALUES (2);"),
+        ("003_never_run", "INSERT INTO t VALUES (3);"),
+    ];
+    let result = run_migrations(&conn, &migrations);
+    assert!(result.is_err());
+    //first migration stays applied, the failing one is rolled back completely and later ones are not run
+    let rows: i64 = conn.query_row("SELECT COUNT(*) FROM t", [], |row| row.get(0)).unwrap();
+    assert_eq!(rows, 0);
+    let versions: Vec<String> = conn
+        .prepare("SELECT version_id FROM _migrations ORDER BY version_id").unwrap()
+        .query_map([], |row| row.get(0)).unwrap()
+        .collect::<std::result::Result<Vec<String>, Error>>().unwrap();
+    assert_eq!(versions, vec![String::from("001_create_t")]);
+}
+
+#[test]
+fn test_enable_wal_mode() {
+    let dbfilepath = std::env::temp_dir().join("helper_lib_test_wal_mode.db");
+    let conn = Connection::open(&dbfilepath).unwrap();
+    enable_wal_mode(&conn).unwrap();
+    let mode: String = conn.query_row("PRAGMA journal_mode", [], |row| row.get(0)).unwrap();
+    //switch back so the -wal and -shm files are removed when the connection closes
+    conn.pragma_update(None, "journal_mode", "DELETE").unwrap();
+    drop(conn);
+    //clean up temp db file
+    if dbfilepath.exists() {
+        fs::remove_file(dbfilepath).unwrap();
+    }
+    assert_eq!(mode, "wal");
+}
+
+#[test]
+fn test_set_busy_timeout() {
+    let conn = Connection::open_in_memory().unwrap();
+    set_busy_timeout(&conn, 2500).unwrap();
+    let timeout: i64 = conn.query_row("PRAGMA busy_timeout", [], |row| row.get(0)).unwrap();
+    assert_eq!(timeout, 2500);
+}
+
+#[test]
+fn test_set_foreign_keys() {
+    let conn = Connection::open_in_memory().unwrap();
+    set_foreign_keys(&conn, true).unwrap();
+    let enabled: bool = conn.query_row("PRAGMA foreign_keys", [], |row| row.get(0)).unwrap();
+    set_foreign_keys(&conn, false).unwrap();
+    let disabled: bool = conn.query_row("PRAGMA foreign_keys", [], |row| row.get(0)).unwrap();
+    assert!(enabled);
+    assert!(!disabled);
+}
+
+#[test]
+fn test_open_in_memory() {
+    let conn = open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE t(c INTEGER, name TEXT); INSERT INTO t VALUES (1, 'one'); INSERT INTO t VALUES (2, 'two');").unwrap();
+    assert!(table_exists(&conn, "t").unwrap());
+    let result = query_to_tuples_conn::<(i64,String)>(&conn, "SELECT c, name FROM t ORDER BY c;").unwrap();
+    let expected: Vec<(i64,String)> = vec![(1, String::from("one")), (2, String::from("two"))];
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_query_exists() {
+    let dbfilepath = PathBuf::from("./tests/resources/test.db");
+    let result = query_exists(&dbfilepath, "SELECT c FROM t WHERE c = 2;").unwrap();
+    assert!(result);
+}
+
+#[test]
+fn test_query_exists_no_match() {
+    let dbfilepath = PathBuf::from("./tests/resources/test.db");
+    let result = query_exists(&dbfilepath, "SELECT c FROM t WHERE c = 99").unwrap();
+    assert!(!result);
+}
+
+#[test]
+fn test_query_exists_conn() {
+    let conn = open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE t(c INTEGER); INSERT INTO t VALUES (1);").unwrap();
+    assert!(query_exists_conn(&conn, "SELECT 1 FROM t WHERE c = 1").unwrap());
+    assert!(!query_exists_conn(&conn, "SELECT 1 FROM t WHERE c = 2").unwrap());
+}
+
+#[test]
+fn test_batch_insert() {
+    let conn = open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE t(id INTEGER, name TEXT);").unwrap();
+    let rows: Vec<Vec<String>> = (0..1000)
+        .map(|i| vec![dbfmt_t(&i), dbfmt_t(&format!("name {}", i))])
+        .collect();
+    let result = batch_insert(&conn, "INSERT INTO t (id, name) VALUES (?1, ?2)", &rows).unwrap();
+    let count: i64 = conn.query_row("SELECT COUNT(*) FROM t", [], |row| row.get(0)).unwrap();
+    let max_id: i64 = conn.query_row("SELECT MAX(id) FROM t WHERE typeof(id) = 'integer'", [], |row| row.get(0)).unwrap();
+    let name: String = conn.query_row("SELECT name FROM t WHERE id = 7", [], |row| row.get(0)).unwrap();
+    assert_eq!(result, 1000);
+    assert_eq!(count, 1000);
+    assert_eq!(max_id, 999);
+    assert_eq!(name, "name 7");
+}
+
+#[test]
+fn test_batch_insert_dbfmt_literals() {
+    let conn = open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE t(name TEXT, amount REAL, note TEXT, created TEXT);").unwrap();
+    let created = Utc.with_ymd_and_hms(2024, 3, 1, 12, 30, 0).unwrap();
+    let rows = vec![vec![dbfmt_t(&"it's"), dbfmt_t(&1.5), dbfmt(None::<String>), dbfmt_t(&created)]];
+    batch_insert(&conn, "INSERT INTO t VALUES (?, ?, ?, ?)", &rows).unwrap();
+    let result = query_to_tuples_conn::<(String, f64, Option<String>, String)>(&conn, "SELECT name, amount, note, created FROM t").unwrap();
+    assert_eq!(result, vec![(String::from("it's"), 1.5, None, String::from("2024-03-01 12:30:00"))]);
+}
+
+#[test]
+fn test_batch_insert_faster_than_autocommit() {
+    let dbfilepath = std::env::temp_dir().join("helper_lib_test_batch_insert_speed.db");
+    _ = fs::remove_file(&dbfilepath);
+    let conn = Connection::open(&dbfilepath).unwrap();
+    conn.execute_batch("CREATE TABLE t(id INTEGER, name TEXT);").unwrap();
+    let rows: Vec<Vec<String>> = (0..1000)
+        .map(|i| vec![dbfmt_t(&i), dbfmt_t(&format!("name {}", i))])
+        .collect();
+    //naive: one autocommitted INSERT per row, each its own transaction
+    let start = std::time::Instant::now();
+    for row in &rows {
+        conn.execute(&format!("INSERT INTO t (id, name) VALUES ({}, {})", row[0], row[1]), []).unwrap();
+    }
+    let naive = start.elapsed();
+    let start = std::time::Instant::now();
+    batch_insert(&conn, "INSERT INTO t (id, name) VALUES (?1, ?2)", &rows).unwrap();
+    let batched = start.elapsed();
+    let count: i64 = conn.query_row("SELECT COUNT(*) FROM t", [], |row| row.get(0)).unwrap();
+    drop(conn);
+    //clean up temp db file
+    _ = fs::remove_file(&dbfilepath);
+    assert_eq!(count, 2000);
+    assert!(batched < naive, "batch_insert took {:?}, row-by-row autocommit took {:?}", batched, naive);
+}
+
+#[test]
+fn test_batch_insert_rollback() {
+    let conn = open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE t(id INTEGER PRIMARY KEY, name TEXT);").unwrap();
+    let rows: Vec<Vec<String>> = vec![
+        vec![String::from("1"), String::from("one")],
+        vec![String::from("1"), String::from("duplicate")],
+    ];
+    let result = batch_insert(&conn, "INSERT INTO t (id, name) VALUES (?1, ?2)", &rows);
+    let count: i64 = conn.query_row("SELECT COUNT(*) FROM t", [], |row| row.get(0)).unwrap();
+    assert!(result.is_err());
+    assert_eq!(count, 0);
+}
+
+#[test]
+fn test_upsert() {
+    let conn = open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE settings(key TEXT PRIMARY KEY, value TEXT, hits INTEGER);").unwrap();
+    let columns = ["key", "value", "hits"];
+    upsert(&conn, "settings", &columns, &[dbfmt_t(&"theme"), dbfmt_t(&"light"), dbfmt_t(&1)], &["key"]).unwrap();
+    let changed = upsert(&conn, "settings", &columns, &[dbfmt_t(&"theme"), dbfmt_t(&"it's dark"), dbfmt_t(&2)], &["key"]).unwrap();
+    let result = query_to_tuples_conn::<(String,String,i64)>(&conn, "SELECT key, value, hits FROM settings").unwrap();
+    assert_eq!(changed, 1);
+    assert_eq!(result, vec![(String::from("theme"), String::from("it's dark"), 2)]);
+}
+
+#[test]
+fn test_upsert_only_conflict_columns() {
+    let conn = open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE tags(name TEXT PRIMARY KEY);").unwrap();
+    let first = upsert(&conn, "tags", &["name"], &[dbfmt_t(&"rust")], &["name"]).unwrap();
+    let second = upsert(&conn, "tags", &["name"], &[dbfmt_t(&"rust")], &["name"]).unwrap();
+    assert_eq!(first, 1);
+    assert_eq!(second, 0);
+}
+
+#[test]
+fn test_upsert_mismatched_values() {
+    let conn = open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE tags(name TEXT PRIMARY KEY);").unwrap();
+    let result = upsert(&conn, "tags", &["name"], &[], &["name"]);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_for_each_row() {
+    let conn = open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE t(c INTEGER); INSERT INTO t VALUES (1); INSERT INTO t VALUES (2); INSERT INTO t VALUES (39);").unwrap();
+    let mut total: i64 = 0;
+    let mut calls: usize = 0;
+    for_each_row(&conn, "SELECT c FROM t", |(c,): (i64,)| {
+        total += c;
+        calls += 1;
+    }).unwrap();
+    assert_eq!(total, 42);
+    assert_eq!(calls, 3);
+}
+
+#[test]
+fn test_for_each_row_conversion_error() {
+    let conn = open_in_memory().unwrap();
+    let result = for_each_row(&conn, "SELECT 'text'", |_: (i64,)| {});
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_query_to_csv() {
+    let conn = open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE t(id INTEGER, comment TEXT);
+        INSERT INTO t VALUES (1, 'plain');
+        INSERT INTO t VALUES (2, 'Smith, \"Jr\"');
+        INSERT INTO t VALUES (3, NULL);").unwrap();
+    let mut output: Vec<u8> = Vec::new();
+    let result = query_to_csv(&conn, "SELECT id, comment AS \"the, comment\" FROM t ORDER BY id", &mut output).unwrap();
+    let expected = "id,\"the, comment\"\r\n1,plain\r\n2,\"Smith, \"\"Jr\"\"\"\r\n3,\r\n";
+    assert_eq!(result, 3);
+    assert_eq!(String::from_utf8(output).unwrap(), expected);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_query_to_value_maps() {
+    let conn = open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE t(name TEXT, qty INTEGER, price REAL);
+        INSERT INTO t VALUES ('pear', 3, 1.25);
+        INSERT INTO t VALUES ('fig', NULL, 0.5);").unwrap();
+    let result = query_to_value_maps(&conn, "SELECT qty, name, price FROM t ORDER BY name").unwrap();
+    assert_eq!(result.len(), 2);
+    //keys stay in column order
+    assert_eq!(result[0].keys().collect::<Vec<&String>>(), vec!["qty", "name", "price"]);
+    assert_eq!(result[0]["name"], serde_json::json!("fig"));
+    assert_eq!(result[0]["qty"], serde_json::Value::Null);
+    assert_eq!(result[1]["qty"], serde_json::json!(3));
+    assert_eq!(result[1]["price"], serde_json::json!(1.25));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_query_to_json() {
+    let conn = open_in_memory().unwrap();
+    let sql = "SELECT 42 AS i, 2.5 AS r, 'text' AS t, x'ff00' AS b, NULL AS n";
+    let result = query_to_json(&conn, sql).unwrap();
+    let expected = serde_json::json!([
+        {"i": 42, "r": 2.5, "t": "text", "b": "/wA=", "n": null}
+    ]);
+    assert_eq!(result, expected);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_query_to_json_no_rows() {
+    let conn = open_in_memory().unwrap();
+    let result = query_to_json(&conn, "SELECT 1 AS c WHERE 1=2").unwrap();
+    assert_eq!(result, serde_json::json!([]));
+}
+
+#[test]
+fn test_query_to_tuples_params_injection_is_data() {
+    let conn = open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE users(id INTEGER, name TEXT);").unwrap();
+    let malicious = "x'; DROP TABLE users; --";
+    conn.execute("INSERT INTO users VALUES (?1, ?2)", (1, malicious)).unwrap();
+    let result = query_to_tuples_params::<(i64,String)>(&conn, "SELECT id, name FROM users WHERE name = ?1", &[&malicious]).unwrap();
+    assert_eq!(result, vec![(1, String::from(malicious))]);
+    assert!(table_exists(&conn, "users").unwrap());
+}
+
+#[test]
+fn test_query_to_tuples_paged() {
+    let conn = open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE t(c INTEGER, name TEXT); INSERT INTO t VALUES (1, 'one'), (2, 'two'), (3, 'three');").unwrap();
+    let sql = "SELECT c, name FROM t ORDER BY c;";
+    let first = query_to_tuples_paged::<(i64,String)>(&conn, sql, 2, 0).unwrap();
+    assert_eq!(first, vec![(1, String::from("one")), (2, String::from("two"))]);
+    let second = query_to_tuples_paged::<(i64,String)>(&conn, sql, 2, 2).unwrap();
+    assert_eq!(second, vec![(3, String::from("three"))]);
+    let past_end = query_to_tuples_paged::<(i64,String)>(&conn, sql, 2, 4).unwrap();
+    assert!(past_end.is_empty());
+}
+
+#[test]
+fn test_query_single_row_to_tuple_params() {
+    let conn = open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE t(c INTEGER, name TEXT); INSERT INTO t VALUES (1, 'one'); INSERT INTO t VALUES (2, 'two');").unwrap();
+    let result = query_single_row_to_tuple_params::<(String,)>(&conn, "SELECT name FROM t WHERE c = ?1", &[&2]).unwrap();
+    let no_rows = query_single_row_to_tuple_params::<(String,)>(&conn, "SELECT name FROM t WHERE c = ?1", &[&3]).unwrap();
+    assert_eq!(result, Some((String::from("two"),)));
+    assert_eq!(no_rows, None);
+}
+
+#[test]
+fn test_query_to_i64_params() {
+    let conn = open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE t(c INTEGER, name TEXT); INSERT INTO t VALUES (7, 'seven');").unwrap();
+    let malicious = "seven' OR '1'='1";
+    let result = query_to_i64_params(&conn, "SELECT c FROM t WHERE name = ?1", &[&"seven"]).unwrap();
+    let injected = query_to_i64_params(&conn, "SELECT c FROM t WHERE name = ?1", &[&malicious]).unwrap();
+    assert_eq!(result, Some(7));
+    assert_eq!(injected, None);
+}
+
+#[test]
+fn test_query_to_string_params() {
+    let conn = open_in_memory().unwrap();
+    let value = "'; DROP TABLE t; --";
+    let result = query_to_string_params(&conn, "SELECT ?1", &[&value]).unwrap();
+    assert_eq!(result, Some(String::from(value)));
+}
+
+#[test]
+fn test_count_rows() {
+    let conn = open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE t(c INTEGER); INSERT INTO t VALUES (1); INSERT INTO t VALUES (2); INSERT INTO t VALUES (5);").unwrap();
+    let result = count_rows(&conn, "t", None).unwrap();
+    assert_eq!(result, 3);
+}
+
+#[test]
+fn test_count_rows_where() {
+    let conn = open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE t(c INTEGER); INSERT INTO t VALUES (1); INSERT INTO t VALUES (2); INSERT INTO t VALUES (5);").unwrap();
+    let where_clause = format!("c{}", dbfmt_comp(Some(1), CompOp::Gt));
+    let result = count_rows(&conn, "t", Some(&where_clause)).unwrap();
+    assert_eq!(result, 2);
+}
+
+#[test]
+fn test_count_rows_table_injection() {
+    let conn = open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE t(c INTEGER);").unwrap();
+    let result = count_rows(&conn, "t; DROP TABLE t", None);
+    assert!(result.is_err());
+    assert!(table_exists(&conn, "t").unwrap());
+}
+
+#[test]
+fn test_open_read_only() {
+    let dbfilepath = PathBuf::from("./tests/resources/test.db");
+    let conn = open_read_only(&dbfilepath).unwrap();
+    let count = count_rows(&conn, "t", None).unwrap();
+    let write = conn.execute("INSERT INTO t VALUES (4)", []);
+    assert_eq!(count, 3);
+    assert!(write.is_err());
+}
+
+#[test]
+fn test_open_read_only_missing_file() {
+    let dbfilepath = std::env::temp_dir().join("helper_lib_test_read_only_missing.db");
+    let result = open_read_only(&dbfilepath);
+    assert!(result.is_err());
+    assert!(!dbfilepath.exists());
+}
+
+#[test]
+fn test_backup_to() {
+    let dbfilepath = std::env::temp_dir().join("helper_lib_test_backup_to.db");
+    let conn = open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE t(c INTEGER); INSERT INTO t VALUES (1); INSERT INTO t VALUES (2); INSERT INTO t VALUES (3);").unwrap();
+    backup_to(&conn, &dbfilepath).unwrap();
+    let source_count = count_rows(&conn, "t", None).unwrap();
+    let backup_count = query_to_i64(&dbfilepath, "SELECT COUNT(*) FROM t").unwrap();
+    //clean up temp db file
+    if dbfilepath.exists() {
+        fs::remove_file(dbfilepath).unwrap();
+    }
+    assert_eq!(source_count, 3);
+    assert_eq!(backup_count, Some(source_count));
+}
+
+#[test]
+fn test_query_to_hashmap() {
+    let conn = open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE settings(key TEXT, value INTEGER);
+        INSERT INTO settings VALUES ('retries', 3);
+        INSERT INTO settings VALUES ('timeout_ms', 2500);").unwrap();
+    let result = query_to_hashmap::<String, i64>(&conn, "SELECT key, value FROM settings").unwrap();
+    let mut expected: HashMap<String, i64> = HashMap::new();
+    expected.insert(String::from("retries"), 3);
+    expected.insert(String::from("timeout_ms"), 2500);
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_query_to_hashmap_bad_value_type() {
+    let conn = open_in_memory().unwrap();
+    let result = query_to_hashmap::<String, i64>(&conn, "SELECT 'key', 'not a number'");
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_query_single_value() {
+    let conn = open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE t(i INTEGER, r REAL, s TEXT, n INTEGER);
+        INSERT INTO t VALUES (42, 2.5, 'hello', NULL);").unwrap();
+    assert_eq!(query_single_value::<i64>(&conn, "SELECT i FROM t").unwrap(), Some(42));
+    assert_eq!(query_single_value::<f64>(&conn, "SELECT r FROM t").unwrap(), Some(2.5));
+    assert_eq!(query_single_value::<String>(&conn, "SELECT s FROM t").unwrap(), Some(String::from("hello")));
+    assert_eq!(query_single_value::<Option<i64>>(&conn, "SELECT n FROM t").unwrap(), Some(None));
+    assert_eq!(query_single_value::<i64>(&conn, "SELECT i FROM t WHERE i = 0").unwrap(), None);
+    assert!(query_single_value::<i64>(&conn, "SELECT n FROM t").is_err());
+}
+
+#[test]
+fn test_delete_rows() {
+    let conn = open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE t(c INTEGER, d INTEGER);
+        INSERT INTO t VALUES (1, NULL), (2, 5), (3, NULL), (4, NULL);").unwrap();
+    let where_clause = where_sql!("{} AND {}", ("c", dbfmt_comp(Some(3), CompOp::Lt)), ("d", dbfmt_comp::<i64>(None, CompOp::Eq)));
+    let deleted = delete_rows(&conn, "t", &where_clause).unwrap();
+    assert_eq!(deleted, 1);
+    assert_eq!(count_rows(&conn, "t", None).unwrap(), 3);
+    assert_eq!(delete_rows(&conn, "t", "c = 99").unwrap(), 0);
+}
+
+#[test]
+fn test_delete_rows_requires_where() {
+    let conn = open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE t(c INTEGER);
+        INSERT INTO t VALUES (1), (2);").unwrap();
+    assert!(matches!(delete_rows(&conn, "t", ""), Err(HelperError::InvalidInput(_))));
+    assert!(matches!(delete_rows(&conn, "t", "   "), Err(HelperError::InvalidInput(_))));
+    assert_eq!(count_rows(&conn, "t", None).unwrap(), 2);
+}
+
+#[test]
+fn test_dbfmt_comp_in() {
+    assert_eq!(dbfmt_comp_in(Some(&[1, 2, 3][..]), false), " IN (1, 2, 3)");
+    assert_eq!(dbfmt_comp_in(Some(&["a", "O'Brien"][..]), true), " NOT IN ('a', 'O''Brien')");
+}
+
+#[test]
+fn test_dbfmt_comp_in_empty_and_none() {
+    assert_eq!(dbfmt_comp_in::<i64>(Some(&[]), false), " IN ()");
+    assert_eq!(dbfmt_comp_in::<i64>(None, false), " IN ()");
+    assert_eq!(dbfmt_comp_in::<i64>(None, true), " NOT IN ()");
+
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE t (c INTEGER); INSERT INTO t VALUES (1), (2), (3);").unwrap();
+    let count = |where_clause: String| -> i64 {
+        conn.query_row(&format!("SELECT COUNT(*) FROM t WHERE {}", where_clause), [], |row| row.get(0)).unwrap()
+    };
+    assert_eq!(count(format!("c{}", dbfmt_comp_in(Some(&[1, 3][..]), false))), 2);
+    assert_eq!(count(format!("c{}", dbfmt_comp_in(Some(&[1, 3][..]), true))), 1);
+    assert_eq!(count(format!("c{}", dbfmt_comp_in::<i64>(None, false))), 0);
+    assert_eq!(count(format!("c{}", dbfmt_comp_in::<i64>(None, true))), 3);
+}
+
+#[test]
+fn test_print_table() {
+    let conn = open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE t(id INTEGER, name TEXT);
+        INSERT INTO t VALUES (1, 'apple');
+        INSERT INTO t VALUES (10, NULL);
+        INSERT INTO t VALUES (3, 'e\u{301}clair');").unwrap();
+    let mut output: Vec<u8> = Vec::new();
+    print_table(&conn, "SELECT id, name FROM t ORDER BY id", &mut output).unwrap();
+    //the combining accent doesn't count towards the width
+    let expected = "id | name\n---+-------\n1  | apple\n3  | e\u{301}clair\n10 | NULL\n";
+    assert_eq!(String::from_utf8(output).unwrap(), expected);
+}
+
+#[test]
+fn test_print_table_truncates_long_cells() {
+    let conn = open_in_memory().unwrap();
+    let mut output: Vec<u8> = Vec::new();
+    print_table(&conn, &format!("SELECT '{}' AS long, 1 AS n", "x".repeat(50)), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines[1], format!("{}-+--", "-".repeat(40)));
+    assert_eq!(lines[2], format!("{}… | 1", "x".repeat(39)));
+}
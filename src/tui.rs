@@ -0,0 +1,942 @@
+#[cfg(target_os = "windows")]
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    terminal,
+};
+use std::{
+    io::{self, BufRead, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+use log::warn;
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+use std::io::Read;
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+use termios::{Termios, TCSANOW, ECHO, ICANON, ISIG, VMIN, VTIME, cc_t, tcflag_t, tcsetattr};
+
+/// blocks until the quit key `q` (or `Q`) is pressed, then sets `keep_going` to false.
+/// Also returns, within about 100ms, once anything else sets `keep_going` to false, so the app can shut down without a key press
+pub fn watch_for_quit(keep_going: Arc<AtomicBool>) {
+    watch_for_key(keep_going, 'q');
+}
+
+/// runs watch_for_quit on a background thread so the calling thread can keep working. The terminal is switched to raw mode
+/// and restored on that thread. Clear `keep_going` (or press `q`) then `.join()` the handle at shutdown
+pub fn spawn_quit_watcher(keep_going: Arc<AtomicBool>) -> JoinHandle<()> {
+    thread::spawn(move || watch_for_quit(keep_going))
+}
+
+/// animates a `|/-\` spinner followed by `message` on the current line while `keep_going` is true, then clears the line.
+/// Pass it a clone of the flag given to spawn_quit_watcher so pressing `q` stops both
+///
+/// # Example
+/// ```no_run
+/// use std::sync::{atomic::AtomicBool, Arc};
+/// let keep_going = Arc::new(AtomicBool::new(true));
+/// let watcher = helper_lib::spawn_quit_watcher(Arc::clone(&keep_going));
+/// helper_lib::spinner_until(Arc::clone(&keep_going), "working, press q to stop");
+/// watcher.join().unwrap();
+/// ```
+pub fn spinner_until(keep_going: Arc<AtomicBool>, message: &str) {
+    _ = spin_until(&keep_going, message, &mut io::stdout());
+}
+
+/// spinner_until, drawing to `writer`
+fn spin_until<W: Write>(keep_going: &AtomicBool, message: &str, writer: &mut W) -> io::Result<()> {
+    const FRAMES: [char; 4] = ['|', '/', '-', '\\'];
+    let mut frame: usize = 0;
+    while keep_going.load(Ordering::Relaxed) {
+        write!(writer, "\r{} {}", FRAMES[frame % FRAMES.len()], message)?;
+        writer.flush()?;
+        frame += 1;
+        // redraw every 100ms, but notice keep_going being cleared sooner
+        for _ in 0..5 {
+            if !keep_going.load(Ordering::Relaxed) {
+                break;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+    // overwrite with spaces rather than an ANSI erase so it works on any terminal
+    let width = message.chars().count() + 2;
+    write!(writer, "\r{}\r", " ".repeat(width))?;
+    writer.flush()
+}
+
+/// as watch_for_quit, but quits on `key` instead of `q`, e.g. `'\u{1b}'` for Escape or `'\n'` for Enter.
+/// ASCII letters match either case. A non-ASCII key matches when its full UTF-8 byte sequence arrives as one key press
+/// on Linux (e.g. typed with a compose key or IME), or its `KeyCode::Char` on Windows; whether a terminal can deliver it at all
+/// depends on the keyboard layout.
+pub fn watch_for_key(keep_going: Arc<AtomicBool>, key: char) {
+    watch_for_quit_keys(keep_going, &[key], false);
+}
+
+/// as watch_for_key, but quits on any of `keys`, and also on Ctrl-C if `quit_on_ctrl_c` is set.
+/// Without it Ctrl-C is left to the terminal, which normally kills the process
+pub fn watch_for_quit_keys(keep_going: Arc<AtomicBool>, keys: &[char], quit_on_ctrl_c: bool) {
+    watch_key_events_while(Some(&keep_going), quit_on_ctrl_c, |pressed| {
+        if is_quit_key(pressed, keys, quit_on_ctrl_c) {
+            if let Some(c) = key_to_char(pressed) {
+                println!("Quit key {:?} pressed.", c);
+            }
+            return false;
+        }
+        true
+    });
+
+    keep_going.store(false, Ordering::Relaxed);
+}
+
+/// whether `pressed` is one of `keys` (ASCII letters in either case), or Ctrl-C when `quit_on_ctrl_c` is set
+fn is_quit_key(pressed: Key, keys: &[char], quit_on_ctrl_c: bool) -> bool {
+    if quit_on_ctrl_c && pressed == Key::Char(CTRL_C) {
+        return true;
+    }
+    keys.iter().any(|&key| {
+        match pressed {
+            Key::Char(c) => c.eq_ignore_ascii_case(&key),
+            Key::Esc => key == '\u{1b}',
+            Key::Enter => key == '\n' || key == '\r',
+            _ => false,
+        }
+    })
+}
+
+/// Ctrl-C as a character, as sent by a terminal in raw mode
+const CTRL_C: char = '\u{3}';
+
+/// calls `on_key` with each character typed, in raw mode (no echo, no line buffering), until it returns false.
+/// Escape, Enter and Tab arrive as `'\u{1b}'`, `'\n'` and `'\t'`; arrow keys are skipped, use watch_key_events for those.
+///
+/// # Example
+/// ```no_run
+/// let mut row: i32 = 0;
+/// helper_lib::watch_keys(|key| {
+///     match key {
+///         'j' => row += 1,
+///         'k' => row -= 1,
+///         _ => {},
+///     }
+///     key != 'q'
+/// });
+/// ```
+pub fn watch_keys<F: FnMut(char) -> bool>(mut on_key: F) {
+    watch_key_events_while(None, false, |key| key_to_char(key).is_none_or(&mut on_key));
+}
+
+/// the character watch_keys passes on for `key`, None for keys it skips
+fn key_to_char(key: Key) -> Option<char> {
+    match key {
+        Key::Char(c) => Some(c),
+        Key::Esc => Some('\u{1b}'),
+        Key::Enter => Some('\n'),
+        _ => None,
+    }
+}
+
+/// A key press, as decoded by watch_key_events
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Up,
+    Down,
+    Left,
+    Right,
+    Esc,
+    Enter,
+}
+
+/// as watch_keys, but calls `on_key` with each decoded Key, so arrow keys can be handled too.
+///
+/// # Example
+/// ```no_run
+/// use helper_lib::Key;
+/// let mut row: i32 = 0;
+/// helper_lib::watch_key_events(|key| {
+///     match key {
+///         Key::Down => row += 1,
+///         Key::Up => row -= 1,
+///         _ => {},
+///     }
+///     key != Key::Esc
+/// });
+/// ```
+pub fn watch_key_events<F: FnMut(Key) -> bool>(on_key: F) {
+    watch_key_events_while(None, false, on_key);
+}
+
+/// records each key press as the raw bytes the terminal sent (e.g. `ESC [ A` for Up, or the UTF-8 bytes of a character)
+/// until `stop` is set, returning them in order, e.g. to replay input when testing a TUI. Keys typed together (a paste)
+/// are split into separate presses. The terminal settings are restored afterwards
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub fn record_keys(stop: Arc<AtomicBool>) -> Vec<Vec<u8>> {
+    let stdin = 0;
+    let _termios_guard = match TermiosGuard::new(stdin, ICANON | ECHO) {
+        Ok(guard) => Some(guard),
+        Err(e) => {
+            warn!("record_keys: stdin is not a terminal, reading keys without raw mode: {}", e);
+            None
+        }
+    };
+    record_key_sequences(stdin, &stop)
+}
+
+/// decodes the bytes of a single key press from a terminal in raw mode, e.g. `ESC [ A` is Up.
+/// Returns None for sequences it does not know, or several characters at once.
+pub fn parse_key_sequence(bytes: &[u8]) -> Option<Key> {
+    match bytes {
+        [0x1b] => Some(Key::Esc),
+        // ESC [ is sent in normal cursor mode, ESC O in application cursor mode
+        [0x1b, b'[' | b'O', b'A'] => Some(Key::Up),
+        [0x1b, b'[' | b'O', b'B'] => Some(Key::Down),
+        [0x1b, b'[' | b'O', b'C'] => Some(Key::Right),
+        [0x1b, b'[' | b'O', b'D'] => Some(Key::Left),
+        [0x1b, ..] => None,
+        [b'\r'] | [b'\n'] => Some(Key::Enter),
+        _ => {
+            let mut chars = std::str::from_utf8(bytes).ok()?.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(Key::Char(c)),
+                _ => None,
+            }
+        }
+    }
+}
+
+/// waits up to `timeout` for a single key press and returns it, as the characters watch_keys gives, e.g. for "press any key to continue".
+/// Returns None on timeout, or if stdin is not a terminal. Arrow keys are skipped, and anything else typed in the same burst
+/// (the rest of a paste) is discarded.
+pub fn read_key_timeout(timeout: Duration) -> Option<char> {
+    #[cfg(target_os = "windows")]
+    {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match event::poll(remaining) {
+                Ok(true) => {},
+                Ok(false) | Err(_) => return None,
+            }
+            if let Ok(Event::Key(key_event)) = event::read()
+                && key_event.kind == KeyEventKind::Press
+                && let Some(c) = key_event_to_key(key_event).and_then(key_to_char)
+            {
+                return Some(c);
+            }
+        }
+    }
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    {
+        let stdin = 0;
+        let _termios_guard = TermiosGuard::new(stdin, ICANON | ECHO).ok()?;
+        let mut reader = io::stdin();
+        let deadline = Instant::now() + timeout;
+        loop {
+            // VTIME is in tenths of a second, up to 25.5s, so longer timeouts take several reads
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let tenths = remaining.as_millis().div_ceil(100).min(255) as cc_t;
+            set_read_timeout(stdin, tenths).ok()?;
+            let mut buffer = [0; 64];
+            let len = match reader.read(&mut buffer) {
+                Ok(len) => len,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(_) => return None,
+            };
+            if len > 0 {
+                // take whatever else has already arrived, so an escape sequence is read whole
+                set_read_timeout(stdin, 0).ok()?;
+                let mut key_seq = buffer[..len].to_vec();
+                while let Ok(len @ 1..) = reader.read(&mut buffer) {
+                    key_seq.extend_from_slice(&buffer[..len]);
+                }
+                let key = if key_seq[0] == 0x1b {
+                    parse_key_sequence(&key_seq)
+                } else {
+                    String::from_utf8_lossy(&key_seq).chars().next()
+                        .and_then(|c| parse_key_sequence(c.encode_utf8(&mut [0; 4]).as_bytes()))
+                };
+                if let Some(c) = key.and_then(key_to_char) {
+                    return Some(c);
+                }
+            }
+            if Instant::now() >= deadline {
+                return None;
+            }
+        }
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    {
+        _ = timeout;
+        panic!("Key reading not coded for this OS");
+    }
+}
+
+/// makes a non-canonical read on `fd` return after `tenths` of a second, or as soon as a byte arrives
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn set_read_timeout(fd: i32, tenths: cc_t) -> io::Result<()> {
+    let mut termios = Termios::from_fd(fd)?;
+    termios.c_cc[VMIN] = 0;
+    termios.c_cc[VTIME] = tenths;
+    tcsetattr(fd, TCSANOW, &termios)
+}
+
+/// prints `prompt` and reads a line from the terminal without echoing it, returned without the trailing newline.
+/// The terminal settings are restored afterwards, even on error. Input that is not a terminal (e.g. a pipe) is read as is
+pub fn read_password(prompt: &str) -> io::Result<String> {
+    read_line(prompt, false)
+}
+
+/// prints `prompt` and reads a line, returned without the trailing newline. With `echo` the terminal is left as is, so the
+/// typed text shows and line editing works; without it echo is turned off as in read_password (line editing still works
+/// on linux/macos) and the terminal settings are restored afterwards, even on error
+pub fn read_line(prompt: &str, echo: bool) -> io::Result<String> {
+    print!("{}", prompt);
+    io::stdout().flush()?;
+    #[cfg(target_os = "windows")]
+    {
+        if echo {
+            return read_trimmed_line(&mut io::stdin().lock());
+        }
+        terminal::enable_raw_mode()?;
+        let result = read_password_windows();
+        terminal::disable_raw_mode()?;
+        println!();
+        result
+    }
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    {
+        read_line_unix(0, &mut io::stdin().lock(), echo)
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    {
+        panic!("Line input not coded for this OS");
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn read_password_windows() -> io::Result<String> {
+    let mut password = String::new();
+    loop {
+        if let Event::Key(key_event) = event::read()? {
+            if key_event.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key_event.code {
+                KeyCode::Enter => return Ok(password),
+                KeyCode::Backspace => {
+                    password.pop();
+                },
+                KeyCode::Char('c') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Err(io::Error::new(io::ErrorKind::Interrupted, "password input cancelled"));
+                },
+                KeyCode::Char(c) => password.push(c),
+                _ => {},
+            }
+        }
+    }
+}
+
+/// reads the line from `reader`, with echo turned off on `fd` (if it is a terminal) unless `echo`
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn read_line_unix<R: BufRead>(fd: i32, reader: &mut R, echo: bool) -> io::Result<String> {
+    let termios_guard = if echo { None } else { TermiosGuard::new(fd, ECHO).ok() };
+    let result = read_trimmed_line(reader);
+    if termios_guard.is_some() {
+        //the enter key was not echoed either
+        println!();
+    }
+    drop(termios_guard);
+    result
+}
+
+/// reads a line from `reader` without its trailing `\n` or `\r\n`. UnexpectedEof if the input has already ended
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+fn read_trimmed_line<R: BufRead>(reader: &mut R) -> io::Result<String> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "no line entered before end of input"));
+    }
+    if line.ends_with('\n') {
+        line.pop();
+        if line.ends_with('\r') {
+            line.pop();
+        }
+    }
+    Ok(line)
+}
+
+
+/// core of the key watchers. Also stops once `keep_going` (if given) is cleared, or input ends.
+/// With `ctrl_c_as_key` Ctrl-C is passed to `on_key` as `Key::Char('\u{3}')` instead of interrupting the process
+fn watch_key_events_while<F: FnMut(Key) -> bool>(keep_going: Option<&AtomicBool>, ctrl_c_as_key: bool, mut on_key: F) {
+    let keep_going = || keep_going.is_none_or(|k| k.load(Ordering::Relaxed));
+    #[cfg(target_os = "windows")]
+    {
+        // the console only reports Ctrl-C as a key press in raw mode
+        if ctrl_c_as_key {
+            _ = terminal::enable_raw_mode();
+        }
+        while keep_going() {
+            // event::read() blocks until the next event, so only read once poll says one is ready.
+            // The timeout bounds how long it takes to notice keep_going being cleared
+            match event::poll(Duration::from_millis(100)) {
+                Ok(true) => {},
+                Ok(false) => continue,
+                Err(e) => {
+                    eprintln!("\nInput thread error: {}. Shutting down.", e);
+                    break;
+                }
+            }
+            match event::read() {
+                Ok(Event::Key(key_event)) => {
+                    if key_event.kind == KeyEventKind::Press
+                        && let Some(key) = key_event_to_key(key_event)
+                        && !on_key(key)
+                    {
+                        break; // Exit the input thread loop
+                    }
+                },
+                Ok(_) => {
+                    // Ignore other events (like mouse or resize)
+                },
+                Err(e) => {
+                    eprintln!("\nInput thread error: {}. Shutting down.", e);
+                    break;
+                }
+            }
+        }
+        if ctrl_c_as_key {
+            _ = terminal::disable_raw_mode();
+        }
+    }
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    {
+        let stdin = 0;
+        // no echo and canonical mode, and no SIGINT so Ctrl-C arrives as a byte if wanted.
+        // The guard restores the original settings however this block is left, including a panic
+        let local_flags = if ctrl_c_as_key { ICANON | ECHO | ISIG } else { ICANON | ECHO };
+        let _termios_guard = match TermiosGuard::new(stdin, local_flags) {
+            Ok(guard) => Some(guard),
+            Err(e) => {
+                warn!("watch_keys: stdin is not a terminal, reading keys without raw mode: {}", e);
+                None
+            }
+        };
+
+        key_sequence_loop(stdin, keep_going, |key_seq| {
+            if key_seq[0] == 0x1b {
+                return parse_key_sequence(key_seq).is_none_or(&mut on_key);
+            }
+            // several keys typed within one poll arrive together, so pass each character on in order
+            let mut buf = [0u8; 4];
+            for c in String::from_utf8_lossy(key_seq).chars() {
+                if let Some(key) = parse_key_sequence(c.encode_utf8(&mut buf).as_bytes())
+                    && !on_key(key)
+                {
+                    return false;
+                }
+            }
+            true
+        });
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    {
+        _ = (keep_going, ctrl_c_as_key, &mut on_key);
+        panic!("Key watching not coded for this OS");
+    }
+}
+
+/// groups the bytes read from `fd` into key sequences and calls `on_key_seq` with each until it returns false,
+/// `keep_going` returns false, or input ends. `fd` is only read on this thread while the loop runs, waiting at most 20ms
+/// at a time, so nothing is left reading it (and taking the next key press) once this returns
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn key_sequence_loop<K, F>(fd: i32, keep_going: K, mut on_key_seq: F)
+where
+    K: Fn() -> bool,
+    F: FnMut(&[u8]) -> bool,
+{
+    // show any prompt before waiting for keys
+    _ = io::stdout().flush();
+    let mut key_seq:Vec<u8> = Vec::new();
+    let mut buffer = [0u8; 64];
+    while keep_going() {
+        // bytes already waiting belong to the same key press or burst, so only wait when there is nothing pending
+        let timeout = if key_seq.is_empty() { Duration::from_millis(20) } else { Duration::ZERO };
+        match read_available(fd, timeout, &mut buffer) {
+            Ok(Some(0)) => {
+                //end of input (e.g. stdin is /dev/null when run in the background). Hand over anything read before it
+                if !key_seq.is_empty() {
+                    on_key_seq(&key_seq);
+                }
+                break;
+            }
+            Ok(Some(len)) => {
+                key_seq.extend_from_slice(&buffer[..len]);
+            }
+            Ok(None) => {
+                // nothing more has arrived, so the sequence is complete
+                if !key_seq.is_empty() {
+                    if !on_key_seq(&key_seq) {
+                        break;
+                    }
+                    key_seq.clear();
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => {
+                warn!("key_sequence_loop: reading input failed: {}", e);
+                break;
+            }
+        }
+    }
+}
+
+/// waits up to `timeout` for `fd` to have input, then reads what is available into `buffer`.
+/// None on timeout, Some(0) at end of input. select is used rather than poll, which doesn't support terminals on macOS
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn read_available(fd: i32, timeout: Duration, buffer: &mut [u8]) -> io::Result<Option<usize>> {
+    let mut timeval = libc::timeval {
+        tv_sec: timeout.as_secs() as libc::time_t,
+        tv_usec: timeout.subsec_micros() as libc::suseconds_t,
+    };
+    // SAFETY: read_fds is initialised by FD_ZERO before use, and fd is checked to fit in it
+    let ready = unsafe {
+        if !(0..libc::FD_SETSIZE as i32).contains(&fd) {
+            return Err(io::Error::from(io::ErrorKind::InvalidInput));
+        }
+        let mut read_fds: libc::fd_set = std::mem::zeroed();
+        libc::FD_ZERO(&mut read_fds);
+        libc::FD_SET(fd, &mut read_fds);
+        libc::select(fd + 1, &mut read_fds, std::ptr::null_mut(), std::ptr::null_mut(), &mut timeval)
+    };
+    if ready < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    if ready == 0 {
+        return Ok(None);
+    }
+    // SAFETY: buffer is valid for writes of buffer.len() bytes
+    let len = unsafe { libc::read(fd, buffer.as_mut_ptr().cast(), buffer.len()) };
+    if len < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(Some(len as usize))
+}
+
+/// the key presses read from `fd` until `stop` is set or input ends, see record_keys
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn record_key_sequences(fd: i32, stop: &AtomicBool) -> Vec<Vec<u8>> {
+    let mut presses: Vec<Vec<u8>> = Vec::new();
+    key_sequence_loop(fd, || !stop.load(Ordering::Relaxed), |key_seq| {
+        presses.extend(split_key_presses(key_seq));
+        true
+    });
+    presses
+}
+
+/// splits bytes received together into single key presses: 3 byte `ESC [ x` / `ESC O x` sequences and UTF-8 characters
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn split_key_presses(key_seq: &[u8]) -> Vec<Vec<u8>> {
+    let mut presses: Vec<Vec<u8>> = Vec::new();
+    let mut rest = key_seq;
+    while let Some(&first) = rest.first() {
+        let len = match rest {
+            [0x1b, b'[' | b'O', _, ..] => 3,
+            _ => match first.leading_ones() {
+                2 => 2,
+                3 => 3,
+                4 => 4,
+                // ASCII, or a stray continuation byte
+                _ => 1,
+            },
+        };
+        let (press, remaining) = rest.split_at(len.min(rest.len()));
+        presses.push(press.to_vec());
+        rest = remaining;
+    }
+    presses
+}
+
+#[cfg(target_os = "windows")]
+fn key_event_to_key(key_event: KeyEvent) -> Option<Key> {
+    if key_event.code == KeyCode::Char('c') && key_event.modifiers.contains(KeyModifiers::CONTROL) {
+        return Some(Key::Char(CTRL_C));
+    }
+    key_code_to_key(key_event.code)
+}
+
+#[cfg(target_os = "windows")]
+fn key_code_to_key(code: KeyCode) -> Option<Key> {
+    match code {
+        KeyCode::Char(c) => Some(Key::Char(c)),
+        KeyCode::Tab => Some(Key::Char('\t')),
+        KeyCode::Up => Some(Key::Up),
+        KeyCode::Down => Some(Key::Down),
+        KeyCode::Left => Some(Key::Left),
+        KeyCode::Right => Some(Key::Right),
+        KeyCode::Esc => Some(Key::Esc),
+        KeyCode::Enter => Some(Key::Enter),
+        _ => None,
+    }
+}
+
+/// Changes terminal settings for as long as it is alive. Created with the settings of `fd` at that moment, which
+/// `Drop` restores, so the terminal is put back even if the code holding the guard panics or returns early.
+///
+/// # Example
+/// ```no_run
+/// # #[cfg(any(target_os = "linux", target_os = "macos"))]
+/// # {
+/// use termios::{ECHO, ICANON};
+/// let _guard = helper_lib::TermiosGuard::new(0, ICANON | ECHO).unwrap();
+/// // stdin is unbuffered with no echo here
+/// # }
+/// // original settings restored when _guard goes out of scope
+/// ```
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub struct TermiosGuard {
+    fd: i32,
+    original: Termios,
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+impl TermiosGuard {
+    /// saves the settings of `fd` then clears `local_flags` (e.g. `ICANON | ECHO`) from its local modes.
+    /// Errors if `fd` is not a terminal
+    pub fn new(fd: i32, local_flags: tcflag_t) -> io::Result<TermiosGuard> {
+        let original = Termios::from_fd(fd)?;
+        let mut new_termios = original;  // make a mutable copy of termios that we will modify
+        new_termios.c_lflag &= !local_flags;
+        tcsetattr(fd, TCSANOW, &new_termios)?;
+        Ok(TermiosGuard { fd, original })
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+impl Drop for TermiosGuard {
+    fn drop(&mut self) {
+        // reset to the original termios data. Nothing useful can be done with an error while dropping
+        _ = tcsetattr(self.fd, TCSANOW, &self.original);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_key_sequence_arrows() {
+        assert_eq!(parse_key_sequence(&[0x1b, b'[', b'A']), Some(Key::Up));
+        assert_eq!(parse_key_sequence(&[0x1b, b'[', b'B']), Some(Key::Down));
+        assert_eq!(parse_key_sequence(&[0x1b, b'[', b'C']), Some(Key::Right));
+        assert_eq!(parse_key_sequence(&[0x1b, b'[', b'D']), Some(Key::Left));
+        assert_eq!(parse_key_sequence(&[0x1b, b'O', b'A']), Some(Key::Up));
+    }
+
+    #[test]
+    fn test_parse_key_sequence_other() {
+        assert_eq!(parse_key_sequence(&[0x1b]), Some(Key::Esc));
+        assert_eq!(parse_key_sequence(b"\n"), Some(Key::Enter));
+        assert_eq!(parse_key_sequence(b"\r"), Some(Key::Enter));
+        assert_eq!(parse_key_sequence(b"q"), Some(Key::Char('q')));
+        assert_eq!(parse_key_sequence("é".as_bytes()), Some(Key::Char('é')));
+        // F1, two characters, nothing and invalid utf-8
+        assert_eq!(parse_key_sequence(&[0x1b, b'O', b'P']), None);
+        assert_eq!(parse_key_sequence(b"jk"), None);
+        assert_eq!(parse_key_sequence(b""), None);
+        assert_eq!(parse_key_sequence(&[0xff]), None);
+    }
+
+    #[test]
+    fn test_is_quit_key_multiple_keys() {
+        let keys = ['q', 'x', '\u{1b}'];
+        assert!(is_quit_key(Key::Char('q'), &keys, false));
+        assert!(is_quit_key(Key::Char('X'), &keys, false));
+        assert!(is_quit_key(Key::Esc, &keys, false));
+        assert!(!is_quit_key(Key::Char('j'), &keys, false));
+        assert!(!is_quit_key(Key::Up, &keys, false));
+        assert!(!is_quit_key(Key::Char('q'), &[], false));
+    }
+
+    #[test]
+    fn test_is_quit_key_ctrl_c() {
+        //the byte a terminal sends for Ctrl-C once ISIG is off
+        let ctrl_c = parse_key_sequence(&[0x03]).unwrap();
+        assert!(is_quit_key(ctrl_c, &['q'], true));
+        assert!(!is_quit_key(ctrl_c, &['q'], false));
+        assert!(!is_quit_key(Key::Char('c'), &['q'], true));
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn test_key_event_to_key_ctrl_c() {
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert_eq!(key_event_to_key(ctrl_c), Some(Key::Char(CTRL_C)));
+        let c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::NONE);
+        assert_eq!(key_event_to_key(c), Some(Key::Char('c')));
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[test]
+    fn test_key_sequence_loop_chars() {
+        use std::os::fd::AsRawFd;
+        let (reader, mut writer) = io::pipe().unwrap();
+        writer.write_all(b"jk").unwrap();
+        let mut seqs: Vec<Vec<u8>> = Vec::new();
+        let feeder = thread::spawn(move || {
+            //give the loop time to see the input pause so "q" arrives as a separate key
+            thread::sleep(Duration::from_millis(100));
+            writer.write_all(b"q").unwrap();
+            thread::sleep(Duration::from_millis(100));
+            writer.write_all(b"x").unwrap();
+            writer
+        });
+        key_sequence_loop(reader.as_raw_fd(), || true, |seq| {
+            seqs.push(seq.to_vec());
+            seq != b"q"
+        });
+        let _writer = feeder.join().unwrap();
+        assert_eq!(seqs, vec![b"jk".to_vec(), b"q".to_vec()]);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[test]
+    fn test_key_sequence_loop_leaves_later_input_unread() {
+        use std::os::fd::AsRawFd;
+        let (reader, mut writer) = io::pipe().unwrap();
+        let keep_going = Arc::new(AtomicBool::new(true));
+        let stopper = {
+            let keep_going = Arc::clone(&keep_going);
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(100));
+                keep_going.store(false, Ordering::Relaxed);
+            })
+        };
+        let mut calls: usize = 0;
+        key_sequence_loop(reader.as_raw_fd(), || keep_going.load(Ordering::Relaxed), |_| {
+            calls += 1;
+            true
+        });
+        stopper.join().unwrap();
+        assert_eq!(calls, 0);
+        //a key pressed after the loop has returned is still there for the next reader
+        writer.write_all(b"z").unwrap();
+        thread::sleep(Duration::from_millis(100));
+        let mut buffer = [0u8; 4];
+        assert_eq!(read_available(reader.as_raw_fd(), Duration::from_millis(100), &mut buffer).unwrap(), Some(1));
+        assert_eq!(buffer[0], b'z');
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[test]
+    fn test_record_key_sequences() {
+        use std::os::fd::AsRawFd;
+        let (reader, mut writer) = io::pipe().unwrap();
+        writer.write_all("a\x1b[Bé".as_bytes()).unwrap();
+        let feeder = thread::spawn(move || {
+            //a separate burst after the loop has seen the input pause
+            thread::sleep(Duration::from_millis(100));
+            writer.write_all(b"\x1b").unwrap();
+        });
+        let stop = AtomicBool::new(false);
+        let presses = record_key_sequences(reader.as_raw_fd(), &stop);
+        feeder.join().unwrap();
+        let expected: Vec<Vec<u8>> = vec![b"a".to_vec(), b"\x1b[B".to_vec(), "é".as_bytes().to_vec(), b"\x1b".to_vec()];
+        assert_eq!(presses, expected);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[test]
+    fn test_record_key_sequences_stop() {
+        use std::os::fd::AsRawFd;
+        let (reader, mut writer) = io::pipe().unwrap();
+        writer.write_all(b"a").unwrap();
+        let stop = AtomicBool::new(true);
+        assert!(record_key_sequences(reader.as_raw_fd(), &stop).is_empty());
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[test]
+    fn test_key_sequence_loop_disconnect_flushes() {
+        use std::os::fd::AsRawFd;
+        let (reader, mut writer) = io::pipe().unwrap();
+        writer.write_all(&[0x1b, b'[', b'A']).unwrap();
+        drop(writer);
+        let mut seqs: Vec<Vec<u8>> = Vec::new();
+        key_sequence_loop(reader.as_raw_fd(), || true, |seq| {
+            seqs.push(seq.to_vec());
+            true
+        });
+        assert_eq!(seqs, vec![vec![0x1b, b'[', b'A']]);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[test]
+    fn test_key_sequence_loop_keep_going_cleared() {
+        use std::os::fd::AsRawFd;
+        let (reader, _writer) = io::pipe().unwrap();
+        let mut calls: usize = 0;
+        key_sequence_loop(reader.as_raw_fd(), || false, |_| {
+            calls += 1;
+            true
+        });
+        assert_eq!(calls, 0);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    #[test]
+    fn test_read_key_timeout_none() {
+        //nothing is typed during the test, so this times out (or stdin is not a terminal)
+        let start = Instant::now();
+        assert_eq!(read_key_timeout(Duration::from_millis(50)), None);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[test]
+    fn test_read_password_trims_newline() {
+        use std::os::fd::AsRawFd;
+        //not a terminal, so the line is read as is
+        let dev_null = std::fs::File::open("/dev/null").unwrap();
+        let mut input = io::Cursor::new("hunter2\r\nnext line\n");
+        assert_eq!(read_line_unix(dev_null.as_raw_fd(), &mut input, false).unwrap(), "hunter2");
+        let mut input = io::Cursor::new("");
+        assert_eq!(read_line_unix(dev_null.as_raw_fd(), &mut input, false).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[test]
+    fn test_read_password_restores_echo() {
+        use std::os::fd::AsRawFd;
+        let Some(pty) = open_pty() else { return; };
+        let fd = pty.as_raw_fd();
+        let original = Termios::from_fd(fd).unwrap();
+        let mut input = io::Cursor::new("hunter2\n");
+        assert_eq!(read_line_unix(fd, &mut input, false).unwrap(), "hunter2");
+        assert_eq!(Termios::from_fd(fd).unwrap().c_lflag, original.c_lflag);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[test]
+    fn test_read_line_echo_leaves_terminal() {
+        use std::os::fd::AsRawFd;
+        let Some(pty) = open_pty() else { return; };
+        let fd = pty.as_raw_fd();
+        let original = Termios::from_fd(fd).unwrap();
+        let mut input = io::Cursor::new("typed text\r\n");
+        assert_eq!(read_line_unix(fd, &mut input, true).unwrap(), "typed text");
+        assert_eq!(Termios::from_fd(fd).unwrap().c_lflag, original.c_lflag);
+        let mut input = io::Cursor::new("");
+        assert_eq!(read_line_unix(fd, &mut input, true).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(Termios::from_fd(fd).unwrap().c_lflag, original.c_lflag);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[test]
+    fn test_watch_for_quit_smoke() {
+        //with keep_going already cleared the watcher sets up and restores the terminal (if stdin is one) then returns straight away
+        let keep_going = Arc::new(AtomicBool::new(false));
+        watch_for_quit(Arc::clone(&keep_going));
+        assert!(!keep_going.load(Ordering::Relaxed));
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn test_watch_for_quit_returns_when_flag_cleared() {
+        let keep_going = Arc::new(AtomicBool::new(true));
+        let watcher_flag = Arc::clone(&keep_going);
+        let handle = thread::spawn(move || watch_for_quit(watcher_flag));
+        thread::sleep(Duration::from_millis(250));
+        keep_going.store(false, Ordering::Relaxed);
+        handle.join().unwrap();
+        assert!(!keep_going.load(Ordering::Relaxed));
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    #[test]
+    fn test_spawn_quit_watcher_join() {
+        let keep_going = Arc::new(AtomicBool::new(true));
+        let handle = spawn_quit_watcher(Arc::clone(&keep_going));
+        thread::sleep(Duration::from_millis(100));
+        keep_going.store(false, Ordering::Relaxed);
+        handle.join().unwrap();
+        assert!(!keep_going.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_spin_until_stops_when_flag_cleared() {
+        let keep_going = Arc::new(AtomicBool::new(true));
+        let stopper = {
+            let keep_going = Arc::clone(&keep_going);
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(150));
+                keep_going.store(false, Ordering::Relaxed);
+            })
+        };
+        let start = Instant::now();
+        let mut output: Vec<u8> = Vec::new();
+        spin_until(&keep_going, "waiting", &mut output).unwrap();
+        let elapsed = start.elapsed();
+        stopper.join().unwrap();
+        assert!(elapsed < Duration::from_millis(500), "took {:?}", elapsed);
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("\r| waiting\r/ waiting"));
+        //the line is blanked at the end
+        assert!(output.ends_with("\r         \r"));
+    }
+
+    #[test]
+    fn test_spin_until_flag_already_cleared() {
+        let keep_going = AtomicBool::new(false);
+        let mut output: Vec<u8> = Vec::new();
+        spin_until(&keep_going, "x", &mut output).unwrap();
+        assert_eq!(output, b"\r   \r");
+    }
+
+    /// opens a pseudo-terminal master, whose termios settings can be changed like a real terminal's
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    fn open_pty() -> Option<std::fs::File> {
+        std::fs::OpenOptions::new().read(true).write(true).open("/dev/ptmx").ok()
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[test]
+    fn test_termios_guard_restores_on_drop() {
+        use std::os::fd::AsRawFd;
+        let Some(pty) = open_pty() else { return; };
+        let fd = pty.as_raw_fd();
+        let original = Termios::from_fd(fd).unwrap();
+        {
+            let _guard = TermiosGuard::new(fd, ECHO).unwrap();
+            assert_eq!(Termios::from_fd(fd).unwrap().c_lflag & ECHO, 0);
+        }
+        assert_eq!(Termios::from_fd(fd).unwrap().c_lflag, original.c_lflag);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[test]
+    fn test_termios_guard_restores_on_panic() {
+        use std::os::fd::AsRawFd;
+        let Some(pty) = open_pty() else { return; };
+        let fd = pty.as_raw_fd();
+        let original = Termios::from_fd(fd).unwrap();
+        let result = std::panic::catch_unwind(|| {
+            let _guard = TermiosGuard::new(fd, ICANON | ECHO).unwrap();
+            panic!("panic while the terminal is in raw mode");
+        });
+        assert!(result.is_err());
+        assert_eq!(Termios::from_fd(fd).unwrap().c_lflag, original.c_lflag);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[test]
+    fn test_termios_guard_not_a_terminal() {
+        use std::os::fd::AsRawFd;
+        let file = std::fs::File::open("Cargo.toml").unwrap();
+        assert!(TermiosGuard::new(file.as_raw_fd(), ECHO).is_err());
+    }
+}
@@ -0,0 +1,21 @@
+// the logger is process wide, so each logger test lives in its own integration test binary
+
+use helper_lib::setup_logger_custom_colors;
+use log::{info, Level, LevelFilter};
+use simplelog::Color;
+
+#[test]
+fn test_setup_logger_custom() {
+    //an invalid format is rejected before any logger is set
+    assert!(setup_logger_custom_colors(LevelFilter::Info, "[hour", false, &[]).is_err());
+    setup_logger_custom_colors(
+        LevelFilter::Info,
+        "[year]-[month]-[day] [hour]:[minute]:[second]",
+        true,
+        &[(Level::Info, Some(Color::Cyan)), (Level::Warn, None)],
+    ).unwrap();
+    info!("logged with a custom time format");
+    assert_eq!(log::max_level(), LevelFilter::Info);
+    //the logger is already set
+    assert!(setup_logger_custom_colors(LevelFilter::Info, "[hour]", false, &[]).is_err());
+}
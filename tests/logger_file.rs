@@ -0,0 +1,32 @@
+// the logger is process wide, so each logger test lives in its own integration test binary
+
+use helper_lib::setup_logger_with_file;
+use log::{debug, error, info, LevelFilter};
+use std::fs;
+
+#[test]
+fn test_setup_logger_with_file() {
+    let log_path = std::env::temp_dir().join("helper_lib_test_setup_logger_with_file.log");
+    if log_path.exists() {
+        fs::remove_file(&log_path).unwrap();
+    }
+    setup_logger_with_file(LevelFilter::Error, LevelFilter::Info, &log_path).unwrap();
+    info!("info line for the file");
+    debug!("debug line below the file level");
+    error!("error line for both");
+    log::logger().flush();
+
+    let contents = fs::read_to_string(&log_path).unwrap();
+    //clean up temp log file
+    fs::remove_file(&log_path).unwrap();
+    assert!(contents.contains("info line for the file"));
+    assert!(contents.contains("error line for both"));
+    assert!(!contents.contains("debug line below the file level"));
+
+    //a second logger can't be installed
+    let second_path = std::env::temp_dir().join("helper_lib_test_setup_logger_with_file_2.log");
+    assert!(setup_logger_with_file(LevelFilter::Error, LevelFilter::Info, &second_path).is_err());
+    if second_path.exists() {
+        fs::remove_file(&second_path).unwrap();
+    }
+}
@@ -0,0 +1,13 @@
+// the logger is process wide, so each logger test lives in its own integration test binary
+
+use helper_lib::setup_logger_once;
+use log::{info, LevelFilter};
+
+#[test]
+fn test_setup_logger_once_twice() {
+    setup_logger_once(LevelFilter::Info);
+    //second call must not panic, and keeps the first level
+    setup_logger_once(LevelFilter::Debug);
+    info!("logged after two setup_logger_once calls");
+    assert_eq!(log::max_level(), LevelFilter::Info);
+}
@@ -0,0 +1,25 @@
+// the signal handler is process wide, so the shutdown test lives in its own integration test binary
+#![cfg(all(feature = "ctrlc", unix))]
+
+use helper_lib::watch_for_shutdown;
+use std::{
+    process::Command,
+    sync::{atomic::{AtomicBool, Ordering}, Arc},
+    thread,
+    time::{Duration, Instant},
+};
+
+#[test]
+fn test_watch_for_shutdown_sigterm() {
+    let keep_going = Arc::new(AtomicBool::new(true));
+    watch_for_shutdown(keep_going.clone()).unwrap();
+    let status = Command::new("kill").arg("-TERM").arg(std::process::id().to_string()).status().unwrap();
+    assert!(status.success());
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while keep_going.load(Ordering::SeqCst) && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert!(!keep_going.load(Ordering::SeqCst));
+    //only one handler per process
+    assert!(watch_for_shutdown(keep_going).is_err());
+}